@@ -0,0 +1,172 @@
+use pills_game_board::*;
+
+const VIRUS_CLEARED: f32 = 10.0;
+const CHAIN: f32 = 4.0;
+const VIRUS_EXPOSED: f32 = 1.5;
+const HEIGHT: f32 = 0.5;
+const DANGER: f32 = 20.0;
+const HOLE: f32 = 2.0;
+const ADJACENT_COLOR: f32 = 1.0;
+const MISMATCHED_COVER: f32 = 1.0;
+
+// Higher is better. The placement is settled with the board's rules first so
+// clears and the chains they cause are scored on the board the player is left with
+pub fn evaluate<T: Clone + Copy + PartialEq>(placement: &Placement<T>, rules: &MatchRules) -> f32 {
+    let settlement = placement.board.settle_with(rules, |l, r| l.color() == r.color());
+    let board = &settlement.board;
+
+    let mut score = 0.0;
+    score += VIRUS_CLEARED * settlement.viruses_removed() as f32;
+    score += CHAIN * settlement.chains().saturating_sub(1) as f32;
+    score += VIRUS_EXPOSED * exposed_viruses(board) as f32;
+    score -= HOLE * holes(board) as f32;
+
+    let height = stack_height(board);
+    score -= HEIGHT * height as f32;
+    // Anything in the two rows under the spawn point risks blocking the next pill
+    if height + 2 >= board.rows {
+        score -= DANGER;
+    }
+
+    if settlement.steps.is_empty() {
+        let (adjacent, mismatched) = placed_pill_contacts(placement);
+        score += ADJACENT_COLOR * adjacent as f32;
+        score -= MISMATCHED_COVER * mismatched as f32;
+    }
+    score
+}
+
+// Viruses with nothing above them but empty space or pills of their own color
+fn exposed_viruses<T: Clone + Copy + PartialEq>(board: &Board<T>) -> usize {
+    let mut count = 0;
+    for col in 0..board.cols {
+        for row in 0..board.rows {
+            if let Cell::Virus(_, color) = board.get(row, col) {
+                let covered = (row + 1..board.rows)
+                    .map(|above| board.get(above, col))
+                    .any(|cell| cell != Cell::Empty && cell.color() != Some(color));
+                if !covered {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+fn stack_height<T: Clone + Copy + PartialEq>(board: &Board<T>) -> usize {
+    (0..board.cols)
+        .map(|col| (0..board.rows).rev().find(|row| board.get(*row, col) != Cell::Empty).map_or(0, |row| row + 1))
+        .max()
+        .unwrap_or(0)
+}
+
+// Empty cells with something stacked over them in the same column
+fn holes<T: Clone + Copy + PartialEq>(board: &Board<T>) -> usize {
+    let mut count = 0;
+    for col in 0..board.cols {
+        let mut roofed = false;
+        for row in (0..board.rows).rev() {
+            match board.get(row, col) {
+                Cell::Empty if roofed => count += 1,
+                Cell::Empty => {},
+                _ => roofed = true,
+            }
+        }
+    }
+    count
+}
+
+// Neighbors of the locked pill that share a color with the half touching them, and
+// differently colored viruses the pill now sits directly on top of
+fn placed_pill_contacts<T: Clone + Copy + PartialEq>(placement: &Placement<T>) -> (usize, usize) {
+    let board = &placement.board;
+    let (row, col) = placement.pivot;
+    let halves = match board.neighbor(row, col, placement.orientation) {
+        Some(other) => vec![(row, col), other],
+        None => vec![(row, col)],
+    };
+    let (mut adjacent, mut mismatched) = (0, 0);
+    for (row, col) in halves.iter().copied() {
+        let color = board.get(row, col).color();
+        for direction in [Orientation::Above, Orientation::Below, Orientation::Left, Orientation::Right] {
+            let Some(position) = board.neighbor(row, col, direction) else { continue };
+            if halves.contains(&position) {
+                continue;
+            }
+            let cell = board.get(position.0, position.1);
+            if cell != Cell::Empty && cell.color() == color {
+                adjacent += 1;
+            } else if direction == Orientation::Below && matches!(cell, Cell::Virus(_, _)) {
+                mismatched += 1;
+            }
+        }
+    }
+    (adjacent, mismatched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_placement(board: &Board<()>, pivot: (usize, usize), cells: &[((usize, usize), CellColor)]) -> Placement<()> {
+        board.placements(pivot).unwrap().into_iter()
+            .find(|placement| cells.iter().all(|((row, col), color)| {
+                matches!(placement.board.get(*row, *col), Cell::Pill(_, c, _) if c == *color)
+            }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_exposing_a_virus_scores_higher() {
+        let board: Board<()> = "
+            _ R> Y< _
+            _ _  _  _
+            _ _  _  _
+            _ _  _  _
+            _ _  _  _
+            b _  _  _
+        ".parse().unwrap();
+        let rules = MatchRules::default();
+        let covering = find_placement(&board, (5, 1), &[((1, 0), CellColor::RED), ((2, 0), CellColor::YELLOW)]);
+        let exposing = find_placement(&board, (5, 1), &[((0, 3), CellColor::RED), ((1, 3), CellColor::YELLOW)]);
+        assert!(evaluate(&exposing, &rules) > evaluate(&covering, &rules));
+    }
+
+    #[test]
+    fn test_fewer_holes_scores_higher() {
+        let board: Board<()> = "
+            _ G> B< _
+            _ _  _  _
+            _ _  _  _
+            _ _  _  _
+            _ _  _  _
+            g _  _  _
+        ".parse().unwrap();
+        let rules = MatchRules::default();
+        let holey = find_placement(&board, (5, 1), &[((1, 0), CellColor::GREEN), ((1, 1), CellColor::BLUE)]);
+        let flat = find_placement(&board, (5, 1), &[((0, 1), CellColor::GREEN), ((0, 2), CellColor::BLUE)]);
+        assert_eq!(holes(&holey.board), 1);
+        assert_eq!(holes(&flat.board), 0);
+        assert!(evaluate(&flat, &rules) > evaluate(&holey, &rules));
+    }
+
+    #[test]
+    fn test_clearing_viruses_scores_highest() {
+        let board: Board<()> = "
+            _ Rv _ _
+            _ R^ _ _
+            _ _  _ _
+            _ _  _ _
+            r _  _ _
+            r _  _ _
+            r _  _ _
+        ".parse().unwrap();
+        let rules = MatchRules::default();
+        let placements = board.placements((6, 1)).unwrap();
+        let best = placements.iter()
+            .max_by(|l, r| evaluate(l, &rules).total_cmp(&evaluate(r, &rules)))
+            .unwrap();
+        assert_eq!(best.board.settle_with(&rules, |l, r| l.color() == r.color()).viruses_removed(), 3);
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+use bevy::ecs::system::EntityCommands;
+use pills_core::*;
+use pills_game_board::*;
+use rand::prelude::*;
+
+pub use evaluate::*;
+
+mod evaluate;
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Starting), setup_ai_control)
+            .add_systems(Update, (
+                choose_placement,
+                drive_pill,
+                apply_gravity,
+            )
+                    .chain()
+                    .run_if(in_state(GameState::Active))
+            )
+        ;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AiDifficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl AiDifficulty {
+    // Seconds between inputs, roughly how fast a person taps at this level
+    fn input_period(&self) -> f32 {
+        match self {
+            AiDifficulty::Easy => 0.45,
+            AiDifficulty::Normal => 0.25,
+            AiDifficulty::Hard => 0.12,
+        }
+    }
+
+    // Chance of going for one of the next best placements instead of the best one
+    fn mistake_chance(&self) -> f64 {
+        match self {
+            AiDifficulty::Easy => 0.4,
+            AiDifficulty::Normal => 0.15,
+            AiDifficulty::Hard => 0.0,
+        }
+    }
+}
+
+#[derive(Component, Debug, Default)]
+pub struct AiControlled {
+    pub difficulty: AiDifficulty,
+}
+
+// How the board should look once the active pill locks
+#[derive(Component, Deref, DerefMut)]
+struct AiTarget(Board<Entity>);
+
+#[derive(Component, Deref, DerefMut)]
+struct InputTimer(Timer);
+
+fn setup_ai_control(
+    mut commands: Commands,
+    ai_query: Query<(Entity, &AiControlled, &BoardConfig, Option<&RestoreBoard>)>,
+) {
+    for (board, ai, config, restore) in ai_query.iter() {
+        info!("Found a board with ai control: {:?}", board);
+        commands.entity(board)
+            .insert(InputTimer(Timer::from_seconds(ai.difficulty.input_period(), TimerMode::Repeating)))
+            .insert(DropTimer::new(config, restore));
+    }
+}
+
+fn pick_placement<T: Clone + Copy + PartialEq>(
+    placements: Vec<Placement<T>>,
+    config: &BoardConfig,
+    difficulty: AiDifficulty,
+    rng: &mut impl Rng,
+) -> Option<Placement<T>> {
+    let mut scored: Vec<(f32, Placement<T>)> = placements.into_iter()
+        .map(|placement| (evaluate(&placement, &config.match_rules), placement))
+        .collect();
+    scored.sort_by(|(l, _), (r, _)| r.total_cmp(l));
+    let index = if rng.gen_bool(difficulty.mistake_chance()) {
+        rng.gen_range(0..scored.len().clamp(1, 4))
+    } else {
+        0
+    };
+    scored.into_iter().nth(index).map(|(_, placement)| placement)
+}
+
+fn choose_placement(
+    mut commands: Commands,
+    mut boards: Query<(&GameBoard, &BoardConfig, &AiControlled, &mut BoardRng)>,
+    new_pivots: Query<(&BoardPosition, &InBoard), Added<PivotPiece>>,
+) {
+    for (pos, board_id) in new_pivots.iter() {
+        if let Ok((board, config, ai, mut rng)) = boards.get_mut(**board_id) {
+            let Ok(placements) = board.placements((pos.row as usize, pos.column as usize)) else { continue };
+            if let Some(placement) = pick_placement(placements, config, ai.difficulty, &mut **rng) {
+                commands.entity(**board_id).insert(AiTarget(placement.board));
+            }
+        }
+    }
+}
+
+fn insert_input(piece: &mut EntityCommands, input: PillInput) {
+    match input.shift {
+        Some(Orientation::Left) => { piece.insert(Move::Left); },
+        Some(Orientation::Right) => { piece.insert(Move::Right); },
+        _ => {},
+    }
+    match input.rotate {
+        Some(Orientation::Left) => { piece.insert(Rotate::Left); },
+        Some(Orientation::Right) => { piece.insert(Rotate::Right); },
+        _ => {},
+    }
+    if input.drop {
+        piece.insert(Drop);
+    }
+}
+
+fn drive_pill(
+    mut commands: Commands,
+    mut boards: Query<(&GameBoard, &BoardConfig, &AiControlled, &AiTarget, &mut InputTimer, &mut BoardRng)>,
+    pivots: Query<(Entity, &BoardPosition, &InBoard), With<PivotPiece>>,
+    time: Res<Time>,
+) {
+    for (piece_id, pos, board_id) in pivots.iter() {
+        let Ok((board, config, ai, target, mut timer, mut rng)) = boards.get_mut(**board_id) else { continue };
+        if !timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        // The piece position lags the board until core syncs them, so wait for that
+        let pivot = (pos.row as usize, pos.column as usize);
+        if board.get(pivot.0, pivot.1).get() != Some(piece_id) {
+            continue;
+        }
+        let Ok(placements) = board.placements(pivot) else { continue };
+        // Gravity can carry the pill past the path to the target, so pick again when that happens
+        let placement = match placements.iter().find(|placement| placement.board == **target) {
+            Some(placement) => placement.clone(),
+            None => match pick_placement(placements, config, ai.difficulty, &mut **rng) {
+                Some(placement) => {
+                    commands.entity(**board_id).insert(AiTarget(placement.board.clone()));
+                    placement
+                },
+                None => continue,
+            },
+        };
+        if let Some(input) = placement.inputs.first() {
+            insert_input(&mut commands.entity(piece_id), *input);
+        }
+    }
+}
+
+fn apply_gravity(
+    mut commands: Commands,
+    mut drop_timer_query: Query<(&mut DropTimer, &BoardConfig, Option<&SpeedCurve>), With<AiControlled>>,
+    time: Res<Time>,
+    pivots: Query<(Entity, &InBoard), With<PivotPiece>>,
+) {
+    for (piece_id, board_id) in pivots.iter() {
+        if let Ok((mut timer, config, speed)) = drop_timer_query.get_mut(**board_id) {
+            timer.update_period(config, speed);
+            if timer.tick(time.delta()).just_finished() {
+                commands.entity(piece_id).insert(Drop);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hard_ai_picks_the_best_placement() {
+        let board: Board<()> = "
+            _ Bv _ _
+            _ B^ _ _
+            _ _  _ _
+            _ _  _ _
+            _ _  _ b
+            _ _  _ b
+            y _  _ b
+        ".parse().unwrap();
+        let config = BoardConfig::default();
+        let placements = board.placements((6, 1)).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            let placement = pick_placement(placements.clone(), &config, AiDifficulty::Hard, &mut rng).unwrap();
+            assert_eq!(placement.pivot.1, 3);
+        }
+    }
+}
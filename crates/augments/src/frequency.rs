@@ -7,7 +7,7 @@ pub struct Frequency {
 
 pub(crate) fn apply(
     augments: Query<(&Frequency, &InBoard), Added<InBoard>>,
-    mut boards: Query<&mut BoardConfig>,
+    mut boards: Query<&mut BoardConfig, Without<Restored>>,
 ) {
     for (augment, board_id) in &augments {
         if let Ok(mut config) = boards.get_mut(**board_id) {
@@ -6,15 +6,24 @@ pub use frequency::Frequency;
 use resilence::Resilience;
 use potency::Potency;
 pub use volatility::Volatility;
+pub use saved::*;
 use urgency::Urgency;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 mod frequency;
 mod potency;
 mod resilence;
+mod saved;
 mod urgency;
 mod volatility;
 
+// Pieces new to the board, the ones rebuilt from a snapshot already have their augments
+pub(crate) type AddedPiece = (Or<(Added<Pill>, Added<Virus>)>, Without<Restored>);
+
+// A piece and the trait an augment changes on it
+pub(crate) type PieceTrait<'a, T> = (AnyOf<(&'a Pill, &'a Virus)>, &'a mut T, &'a InBoard);
+
 pub fn all_pieces((pill, virus): (Option<&Pill>, Option<&Virus>)) -> bool {
     return pill.is_some() || virus.is_some()
 }
@@ -63,6 +72,61 @@ pub fn yellow_viruses((_, virus): (Option<&Pill>, Option<&Virus>)) -> bool {
     return virus.map(|p| p.0 == CellColor::YELLOW).unwrap_or(false);
 }
 
+// Which pieces an augment changes. Kept as a name rather than the function so it can be compared and saved
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PieceFilter {
+    AllPieces,
+    AllPills,
+    AllViruses,
+    AllRed,
+    AllBlue,
+    AllYellow,
+    RedPills,
+    BluePills,
+    YellowPills,
+    RedViruses,
+    BlueViruses,
+    YellowViruses,
+}
+
+impl PieceFilter {
+    pub fn matches(&self, pieces: (Option<&Pill>, Option<&Virus>)) -> bool {
+        let filter = match self {
+            PieceFilter::AllPieces => all_pieces,
+            PieceFilter::AllPills => all_pills,
+            PieceFilter::AllViruses => all_viruses,
+            PieceFilter::AllRed => all_red,
+            PieceFilter::AllBlue => all_blue,
+            PieceFilter::AllYellow => all_yellow,
+            PieceFilter::RedPills => red_pills,
+            PieceFilter::BluePills => blue_pills,
+            PieceFilter::YellowPills => yellow_pills,
+            PieceFilter::RedViruses => red_viruses,
+            PieceFilter::BlueViruses => blue_viruses,
+            PieceFilter::YellowViruses => yellow_viruses,
+        };
+        filter(pieces)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceFilter::AllPieces => "all pieces",
+            PieceFilter::AllPills => "all pills",
+            PieceFilter::AllViruses => "all viruses",
+            PieceFilter::AllRed => "all red",
+            PieceFilter::AllBlue => "all blue",
+            PieceFilter::AllYellow => "all yellow",
+            PieceFilter::RedPills => "red pills",
+            PieceFilter::BluePills => "blue pills",
+            PieceFilter::YellowPills => "yellow pills",
+            PieceFilter::RedViruses => "red viruses",
+            PieceFilter::BlueViruses => "blue viruses",
+            PieceFilter::YellowViruses => "yellow viruses",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Augment {
     Frequency(Frequency),
@@ -76,10 +140,20 @@ impl Augment {
     pub fn cost(&self) -> u32 {
         match self {
             Augment::Frequency(f) => { 1 },
-            Augment::Potency(p) => if p.filter == all_pills { 3 } else { 1 },
-            Augment::Resilience(r) => if r.filter == all_pills { 3 } else { 1 },
+            Augment::Potency(p) => if p.filter == PieceFilter::AllPills { 3 } else { 1 },
+            Augment::Resilience(r) => if r.filter == PieceFilter::AllPills { 3 } else { 1 },
             Augment::Urgency(_) => 1,
-            Augment::Volatility(v) => if v.filter == all_viruses { 3 } else { 1 },
+            Augment::Volatility(v) => if v.filter == PieceFilter::AllViruses { 3 } else { 1 },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Augment::Frequency(_) => "Frequency",
+            Augment::Potency(_) => "Potency",
+            Augment::Resilience(_) => "Resilience",
+            Augment::Urgency(_) => "Urgency",
+            Augment::Volatility(_) => "Volatility",
         }
     }
 }
@@ -90,6 +164,8 @@ pub struct AugmentIconIndex(pub usize);
 impl EntityCommand for Augment {
     fn apply(self, id: Entity, world: &mut World) {
         info!("Applying augment {:?}", self);
+        world.entity_mut(id).insert(Name::new(self.name()));
+        world.entity_mut(id).insert(self.save());
         match self {
             Augment::Frequency(frequency) => {
                 world.entity_mut(id)
@@ -125,39 +201,39 @@ type AugmentInfo = (&'static str, &'static [&'static Augment]);
 pub const OVERDOSE: AugmentInfo = (
     "Overdose",
     &[
-        &Augment::Resilience(Resilience {amount: 2, filter: all_pills}),
-        &Augment::Potency(Potency {amount: 2, filter: all_pills}),
+        &Augment::Resilience(Resilience {amount: 2, filter: PieceFilter::AllPills}),
+        &Augment::Potency(Potency {amount: 2, filter: PieceFilter::AllPills}),
     ],
 );
 
 pub const SUPERBUG: AugmentInfo = (
     "Superbugs",
-    &[&Augment::Resilience(Resilience {amount: 2, filter: all_viruses})],
+    &[&Augment::Resilience(Resilience {amount: 2, filter: PieceFilter::AllViruses})],
 );
 
-pub fn random_helpful_augment(rng: &mut ThreadRng) -> Augment {
+pub fn random_helpful_augment(rng: &mut impl Rng) -> Augment {
     let amount = rng.gen_range(2..=4);
     match rng.gen_range(0..=1) {
         0 => Augment::Potency(Potency {
             amount, 
-            filter: [all_pills, red_pills, yellow_pills, blue_pills][rng.gen_range(0..=3)]
+            filter: [PieceFilter::AllPills, PieceFilter::RedPills, PieceFilter::YellowPills, PieceFilter::BluePills][rng.gen_range(0..=3)]
         }),
         1 => Augment::Volatility(Volatility { 
             area: AreaOfEffect::Radius(amount-1), 
-            filter: [all_viruses, red_viruses, yellow_viruses, blue_viruses][rng.gen_range(0..=3)]
+            filter: [PieceFilter::AllViruses, PieceFilter::RedViruses, PieceFilter::YellowViruses, PieceFilter::BlueViruses][rng.gen_range(0..=3)]
         }),
         _ => unreachable!()
     }
 }
 
-pub fn random_harmful_augment(rng: &mut ThreadRng) -> Augment {
+pub fn random_harmful_augment(rng: &mut impl Rng) -> Augment {
     match rng.gen_range(0..=2) {
         0 => { 
             let filter = match rng.gen_range(0..=3) {
-                0 => { all_viruses},
-                1 => red_viruses,
-                2 => yellow_viruses,
-                3 => blue_viruses,
+                0 => { PieceFilter::AllViruses},
+                1 => PieceFilter::RedViruses,
+                2 => PieceFilter::YellowViruses,
+                3 => PieceFilter::BlueViruses,
                 _ => unreachable!()
             };
             let amount = 1;
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use super::*;
+
+// An augment as it is written to a save, added to the augment's entity when it is applied
+#[derive(Clone, Component, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SavedAugment {
+    Frequency { amount: i32 },
+    Potency { amount: u8, filter: PieceFilter },
+    Resilience { amount: u8, filter: PieceFilter },
+    Urgency { amount: f32 },
+    Volatility { area: AreaOfEffect, filter: PieceFilter },
+}
+
+impl Augment {
+    pub fn save(&self) -> SavedAugment {
+        match self {
+            Augment::Frequency(f) => SavedAugment::Frequency { amount: f.amount },
+            Augment::Potency(p) => SavedAugment::Potency { amount: p.amount, filter: p.filter },
+            Augment::Resilience(r) => SavedAugment::Resilience { amount: r.amount, filter: r.filter },
+            Augment::Urgency(u) => SavedAugment::Urgency { amount: u.amount },
+            Augment::Volatility(v) => SavedAugment::Volatility { area: v.area, filter: v.filter },
+        }
+    }
+}
+
+impl SavedAugment {
+    pub fn load(&self) -> Augment {
+        match self {
+            SavedAugment::Frequency { amount } => Augment::Frequency(Frequency { amount: *amount }),
+            SavedAugment::Potency { amount, filter } => Augment::Potency(Potency { amount: *amount, filter: *filter }),
+            SavedAugment::Resilience { amount, filter } => Augment::Resilience(Resilience { amount: *amount, filter: *filter }),
+            SavedAugment::Urgency { amount } => Augment::Urgency(Urgency { amount: *amount }),
+            SavedAugment::Volatility { area, filter } => Augment::Volatility(Volatility { area: *area, filter: *filter }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_augments_load_back_the_same() {
+        let augments = [
+            Augment::Frequency(Frequency { amount: 10 }),
+            Augment::Potency(Potency { amount: 3, filter: PieceFilter::RedPills }),
+            Augment::Resilience(Resilience { amount: 1, filter: PieceFilter::BlueViruses }),
+            Augment::Urgency(Urgency { amount: 0.1 }),
+            Augment::Volatility(Volatility { area: AreaOfEffect::Radius(2), filter: PieceFilter::AllViruses }),
+        ];
+        for augment in augments {
+            let saved = augment.save();
+            let written = ron::to_string(&saved).unwrap();
+            let read: SavedAugment = ron::from_str(&written).unwrap();
+            assert_eq!(read, saved);
+            let loaded = read.load();
+            assert_eq!(loaded.save(), saved);
+            assert_eq!(loaded.cost(), augment.cost());
+        }
+    }
+}
@@ -9,7 +9,7 @@ const MIN_DROP_PERIOD: f32 = 0.35;
 
 pub(crate) fn apply(
     augments: Query<(&Urgency, &InBoard), Added<InBoard>>,
-    mut boards: Query<&mut BoardConfig>,
+    mut boards: Query<&mut BoardConfig, Without<Restored>>,
 ) {
     for (augment, board_id) in &augments {
         if let Ok(mut config) = boards.get_mut(**board_id) {
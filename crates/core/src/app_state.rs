@@ -6,5 +6,9 @@ pub enum AppState {
     MainMenu,
     PauseMenu,
     LevelMenu,
+    RunMap,
+    ShopMenu,
+    ControlsMenu,
+    HighScoreMenu,
     InGame,
 }
\ No newline at end of file
@@ -0,0 +1,25 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A file in the user's data directory, or the working directory on platforms without one
+pub fn data_path(file: impl AsRef<Path>) -> PathBuf {
+    let dir = dirs::data_dir().map_or(PathBuf::new(), |dir| dir.join("pills"));
+    dir.join(file)
+}
+
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(ron::from_str(&contents)?)
+}
+
+// Creates the directories on the way to the file if they are missing
+pub fn save_ron<T: Serialize + ?Sized>(path: impl AsRef<Path>, value: &T) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.as_ref().parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?)?;
+    Ok(())
+}
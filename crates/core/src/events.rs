@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use pills_game_board::CellColor;
 use crate::{Move, Pill, Rotate, Virus};
 
 pub(crate) struct EventPlugin;
@@ -42,10 +43,38 @@ pub struct PillMoved {
     pub movement: Movement,
 }
 
+#[derive(Debug)]
+pub struct PillHeld {
+    pub board: Entity,
+    pub pieces: [Entity; 2],
+}
+
+#[derive(Debug)]
+pub struct PillLocked {
+    pub board: Entity,
+    pub piece: Entity,
+}
+
 #[derive(Debug)]
 pub struct CellsCleared {
     pub board: Entity,
     pub count: usize,
+    // How many clears into the cascade this is, starting at 1
+    pub chain: usize,
+    pub groups: usize,
+}
+
+#[derive(Debug)]
+pub struct GarbageSent {
+    pub board: Entity,
+    pub colors: Vec<CellColor>,
+}
+
+#[derive(Debug)]
+pub struct GarbageReceived {
+    pub board: Entity,
+    pub from: Entity,
+    pub colors: Vec<CellColor>,
 }
 
 #[derive(Debug, Event)]
@@ -53,7 +82,11 @@ pub enum BoardEvent {
     PillAdded(PillAdded),
     VirusRemoved(VirusRemoved),
     PillMoved(PillMoved),
+    PillHeld(PillHeld),
+    PillLocked(PillLocked),
     CellsCleared(CellsCleared),
+    GarbageSent(GarbageSent),
+    GarbageReceived(GarbageReceived),
 }
 
 impl Into<Movement> for Move {
@@ -82,7 +115,23 @@ impl BoardEvent {
         Self::PillMoved(PillMoved { board, piece, movement })
     }
 
-    pub(crate) fn cells_cleared(board: Entity, count: usize) -> Self {
-        Self::CellsCleared(CellsCleared { board, count })
+    pub(crate) fn pill_held(board: Entity, pieces: [Entity; 2]) -> Self {
+        Self::PillHeld(PillHeld { board, pieces })
+    }
+
+    pub(crate) fn pill_locked(board: Entity, piece: Entity) -> Self {
+        Self::PillLocked(PillLocked { board, piece })
+    }
+
+    pub(crate) fn cells_cleared(board: Entity, count: usize, chain: usize, groups: usize) -> Self {
+        Self::CellsCleared(CellsCleared { board, count, chain, groups })
+    }
+
+    pub(crate) fn garbage_sent(board: Entity, colors: Vec<CellColor>) -> Self {
+        Self::GarbageSent(GarbageSent { board, colors })
+    }
+
+    pub(crate) fn garbage_received(board: Entity, from: Entity, colors: Vec<CellColor>) -> Self {
+        Self::GarbageReceived(GarbageReceived { board, from, colors })
     }
 }
\ No newline at end of file
@@ -19,9 +19,7 @@ pub(crate) fn start_game(
     for (entity, config) in query.iter() {
         info!("[start_game] Adding board bundle to entity: {:?}", entity);
         commands.entity(entity)
-            .insert(BoardBundle::with_config(config))
-            .insert(NeedsPill)
-            .insert(NeedsSpawn);
+            .insert(BoardBundle::with_config(config));
     }
     state.set(GameState::Active);
 }
\ No newline at end of file
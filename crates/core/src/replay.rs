@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use pills_game_board::*;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::*;
+
+pub const REPLAY_DIR: &str = "replays";
+
+// Finished boards are only written to `REPLAY_DIR` while this is present
+#[derive(Resource)]
+pub struct RecordReplays;
+
+// Plays back a recorded game on the board in place of a player or the ai
+#[derive(Component)]
+pub struct ReplayControlled {
+    pub replay: Replay,
+    pub(crate) tick: usize,
+}
+
+impl ReplayControlled {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, tick: 0 }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.tick >= self.replay.ticks.len()
+    }
+}
+
+pub fn load_replay(path: impl AsRef<Path>) -> Result<Replay, Box<dyn Error>> {
+    load_ron(path)
+}
+
+pub fn save_replay(path: impl AsRef<Path>, replay: &Replay) -> Result<(), Box<dyn Error>> {
+    save_ron(path, replay)
+}
+
+fn replay_path(board_id: Entity, replay: &Replay) -> PathBuf {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    data_path(REPLAY_DIR).join(format!("{}-{}-{}.ron", time, replay.seed, board_id.index()))
+}
+
+type FinishedRecording = (Added<BoardFinished>, Without<ReplayControlled>);
+
+// Every played board is recorded, so write it out once it is over if asked to
+pub(crate) fn save_replays(
+    boards: Query<(Entity, &BoardSimulation), FinishedRecording>,
+) {
+    for (board_id, simulation) in boards.iter() {
+        let Some(replay) = simulation.simulation.recording() else { continue };
+        let path = replay_path(board_id, replay);
+        match save_replay(&path, replay) {
+            Ok(()) => info!("Saved replay to {}", path.display()),
+            Err(err) => warn!("Failed to save replay to {}: {}", path.display(), err),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+// Seed for every random roll in a run, shown on the results screen so the run can be replayed
+#[derive(Clone, Copy, Debug, Deref, PartialEq, Resource)]
+pub struct RunSeed(pub u64);
+
+// Set from the command line to replay the same seed on every run
+#[derive(Clone, Copy, Debug, Deref, PartialEq, Resource)]
+pub struct FixedSeed(pub u64);
+
+// Stream for the rolls made between boards, like level offers and the augments on them
+#[derive(Deref, DerefMut, Resource)]
+pub struct RunRng(pub StdRng);
+
+// Stream owned by a single board for pills, viruses and anything else rolled while it is played
+#[derive(Clone, Component, Deref, DerefMut)]
+pub struct BoardRng(pub StdRng);
+
+impl RunSeed {
+    pub fn new(fixed_seed: Option<&FixedSeed>) -> Self {
+        match fixed_seed {
+            Some(seed) => Self(**seed),
+            None => Self(thread_rng().gen()),
+        }
+    }
+}
+
+impl RunRng {
+    pub fn from_seed(seed: RunSeed) -> Self {
+        Self(StdRng::seed_from_u64(*seed))
+    }
+
+    pub fn resume(state: u64) -> Self {
+        Self(StdRng::seed_from_u64(state))
+    }
+
+    // Restarts the stream from a seed drawn from it, so a saved run can carry on with the same rolls
+    pub fn reseed(&mut self) -> u64 {
+        let state = self.gen();
+        self.0 = StdRng::seed_from_u64(state);
+        state
+    }
+}
+
+impl BoardRng {
+    pub fn from_run(rng: &mut RunRng) -> Self {
+        Self(StdRng::seed_from_u64(rng.gen()))
+    }
+}
+
+// Inserts the seed and stream for a new run, returning the stream so the first level can be built from it
+pub fn start_run(commands: &mut Commands, fixed_seed: Option<&FixedSeed>) -> RunRng {
+    let seed = RunSeed::new(fixed_seed);
+    info!("Starting run with seed {}", *seed);
+    commands.insert_resource(seed);
+    RunRng::from_seed(seed)
+}
@@ -0,0 +1,321 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use pills_game_board::*;
+use rand::{thread_rng, Rng};
+use crate::*;
+
+// The rules engine driving a board, and the entity standing in for each of its pieces
+#[derive(Component)]
+pub struct BoardSimulation {
+    pub simulation: GameSimulation,
+    pub(crate) entities: HashMap<PieceId, Entity>,
+}
+
+impl BoardSimulation {
+    pub fn entity(&self, piece: PieceId) -> Option<Entity> {
+        self.entities.get(&piece).copied()
+    }
+
+    pub fn piece(&self, entity: Entity) -> Option<PieceId> {
+        self.entities.iter().find(|(_, e)| **e == entity).map(|(piece, _)| *piece)
+    }
+}
+
+type NewBoardQuery<'a> = (
+    Entity,
+    &'a mut GameBoard,
+    &'a BoardConfig,
+    &'a VirusSpawner,
+    Option<&'a mut BoardRng>,
+    Option<&'a ReplayControlled>,
+    Option<&'a RestoreBoard>,
+);
+
+pub(crate) fn start_simulations(
+    mut commands: Commands,
+    mut boards: Query<NewBoardQuery, Without<BoardSimulation>>,
+    mut positions: Query<&mut BoardPosition>,
+    mut events: EventWriter<BoardEvent>,
+) {
+    for (board_id, mut board, config, spawner, rng, replay, restore) in boards.iter_mut() {
+        if let Some(restore) = restore {
+            commands.entity(board_id).remove::<RestoreBoard>();
+            match restore_simulation(&mut commands, board_id, &restore.0) {
+                Some(simulation) => {
+                    sync_board(&simulation, &mut board, &mut positions);
+                    commands.entity(board_id).insert(simulation);
+                    continue;
+                },
+                None => warn!("Board {:?} did not play back to its snapshot, starting it over", board_id),
+            }
+        }
+        let (simulation, sim_events) = match replay {
+            Some(replay) => replay.replay.start(),
+            None => {
+                let seed = match rng {
+                    Some(mut rng) => rng.gen(),
+                    None => thread_rng().gen(),
+                };
+                let mut simulation = GameSimulation::recorded(config.simulation_config(spawner), seed);
+                let sim_events = simulation.start();
+                (simulation, sim_events)
+            },
+        };
+        let mut simulation = BoardSimulation { simulation, entities: HashMap::default() };
+        apply_sim_events(&mut commands, board_id, &mut simulation, sim_events, &mut events);
+        sync_board(&simulation, &mut board, &mut positions);
+        commands.entity(board_id).insert(simulation);
+    }
+}
+
+type PivotInput<'a> = AnyOf<(&'a Move, &'a Rotate, &'a Drop, &'a HardDrop, &'a Hold)>;
+
+type PieceTraitsQuery<'a> = (Entity, &'a InBoard, Option<&'a Stacked>, Option<&'a RemoveStack>, Option<&'a Explosive>);
+type ChangedTraits = Or<(Changed<Stacked>, Changed<RemoveStack>, Changed<Explosive>)>;
+
+// Augments change these on the piece entities so they are copied into the simulation
+pub(crate) fn sync_piece_traits(
+    mut boards: Query<&mut BoardSimulation, Without<ReplayControlled>>,
+    pieces: Query<PieceTraitsQuery, ChangedTraits>,
+) {
+    for (entity, board_id, stacked, remove_stack, explosive) in pieces.iter() {
+        if let Ok(mut simulation) = boards.get_mut(**board_id) {
+            let Some(piece) = simulation.piece(entity) else { continue };
+            simulation.simulation.set_traits(piece, PieceTraits {
+                stacks: stacked.map_or(0, |stacked| stacked.0),
+                remove_stacks: remove_stack.map_or(0, |remove_stack| remove_stack.0),
+                explosive: explosive.map_or(AreaOfEffect::default(), |explosive| explosive.0),
+            });
+        }
+    }
+}
+
+pub(crate) fn step_simulations(
+    mut commands: Commands,
+    mut boards: Query<(Entity, &mut GameBoard, &mut BoardSimulation, Option<&mut ReplayControlled>)>,
+    pivots: Query<PivotInput, With<PivotPiece>>,
+    mut positions: Query<&mut BoardPosition>,
+    mut events: EventWriter<BoardEvent>,
+    time: Res<Time>,
+) {
+    let mut sent_garbage = vec![];
+    for (board_id, mut board, mut simulation, replay) in boards.iter_mut() {
+        let sim_events = match replay {
+            Some(mut replay) => {
+                let Some(tick) = replay.replay.ticks.get(replay.tick).cloned() else { continue };
+                replay.tick += 1;
+                apply_replay_traits(&mut commands, &simulation, &tick);
+                Replay::play_tick(&mut simulation.simulation, &tick)
+            },
+            None => step_with_input(&mut commands, &mut simulation, &pivots, time.delta_seconds()),
+        };
+        for sim_event in sim_events.iter() {
+            if let SimEvent::GarbageSent { colors } = sim_event {
+                sent_garbage.push((board_id, colors.clone()));
+            }
+        }
+        apply_sim_events(&mut commands, board_id, &mut simulation, sim_events, &mut events);
+        sync_board(&simulation, &mut board, &mut positions);
+    }
+    // Replays already have the garbage they received recorded, so only live boards take it
+    for (from, colors) in sent_garbage {
+        for (board_id, _, mut simulation, replay) in boards.iter_mut() {
+            if board_id == from || replay.is_some() || simulation.simulation.outcome().is_some() {
+                continue;
+            }
+            simulation.simulation.receive_garbage(&colors);
+            events.send(BoardEvent::garbage_received(board_id, from, colors.clone()));
+        }
+    }
+}
+
+fn step_with_input(
+    commands: &mut Commands,
+    simulation: &mut BoardSimulation,
+    pivots: &Query<PivotInput, With<PivotPiece>>,
+    dt: f32,
+) -> Vec<SimEvent> {
+    let mut input = PillInput::default();
+    let pivot = simulation.simulation.pivot_piece().and_then(|piece| simulation.entity(piece));
+    if let Some((piece_id, Ok((mv, rotate, drop, hard, hold)))) = pivot.map(|piece_id| (piece_id, pivots.get(piece_id))) {
+        input = PillInput {
+            shift: mv.map(|mv| match mv {
+                Move::Left => Orientation::Left,
+                Move::Right => Orientation::Right,
+            }),
+            rotate: rotate.map(|rotate| match rotate {
+                Rotate::Left => Orientation::Left,
+                Rotate::Right => Orientation::Right,
+            }),
+            drop: drop.is_some(),
+            hard_drop: hard.is_some(),
+            hold: hold.is_some(),
+        };
+        commands.entity(piece_id).remove::<(Move, Rotate, Drop, HardDrop, Hold)>();
+    }
+    simulation.simulation.step(input, dt)
+}
+
+// Puts the recorded trait changes back on the pieces so they are drawn the way they were played
+fn apply_replay_traits(commands: &mut Commands, simulation: &BoardSimulation, tick: &ReplayTick) {
+    for (piece, traits) in tick.traits.iter() {
+        let Some(entity) = simulation.entity(*piece) else { continue };
+        commands.entity(entity).insert((
+            Stacked(traits.stacks),
+            RemoveStack(traits.remove_stacks),
+            Explosive(traits.explosive),
+        ));
+    }
+}
+
+fn apply_sim_events(
+    commands: &mut Commands,
+    board_id: Entity,
+    simulation: &mut BoardSimulation,
+    sim_events: Vec<SimEvent>,
+    events: &mut EventWriter<BoardEvent>,
+) {
+    for sim_event in sim_events {
+        match sim_event {
+            SimEvent::VirusSpawned { piece, color, row, col } => {
+                let entity = commands.spawn((
+                    Virus(color),
+                    BoardPosition { row: row as u8, column: col as u8 },
+                    InBoard(board_id),
+                    Stacked(0),
+                    Explosive(AreaOfEffect::Radius(0)),
+                )).set_parent(board_id).id();
+                simulation.entities.insert(piece, entity);
+            },
+            SimEvent::PillQueued { pieces, position } => {
+                for (half, (piece, color)) in pieces.into_iter().enumerate() {
+                    let next_pill = NextPill { position: position as u8, half: half as u8 };
+                    let entity = commands.spawn((Pill(color), next_pill, InBoard(board_id), RemoveStack(0))).id();
+                    simulation.entities.insert(piece, entity);
+                }
+            },
+            SimEvent::PillAdded { pieces, row, col } => {
+                for (index, (piece, color)) in pieces.into_iter().enumerate() {
+                    let Some(entity) = simulation.entity(piece) else { continue };
+                    events.send(BoardEvent::pill_added(board_id, entity, Pill(color)));
+                    commands.entity(entity)
+                        .remove::<(NextPill, HeldPill)>()
+                        .insert(BoardPosition { row: row as u8, column: (col + index) as u8 })
+                        .set_parent(board_id);
+                    if index == 1 {
+                        commands.entity(entity).insert(PivotPiece);
+                    }
+                }
+                // Everything left in the queue moves up a place
+                for (position, pieces) in simulation.simulation.queue().enumerate() {
+                    for (half, (piece, _)) in pieces.iter().enumerate() {
+                        let Some(entity) = simulation.entity(*piece) else { continue };
+                        commands.entity(entity).insert(NextPill { position: position as u8, half: half as u8 });
+                    }
+                }
+            },
+            SimEvent::PillHeld { pieces } => {
+                let [Some(first), Some(second)] = pieces.map(|(piece, _)| simulation.entity(piece)) else { continue };
+                let entities = [first, second];
+                for (half, entity) in entities.into_iter().enumerate() {
+                    commands.entity(entity)
+                        .remove::<(BoardPosition, PivotPiece)>()
+                        .insert(HeldPill { half: half as u8 });
+                }
+                events.send(BoardEvent::pill_held(board_id, entities));
+            },
+            SimEvent::PillShifted { piece, direction } => {
+                let Some(entity) = simulation.entity(piece) else { continue };
+                let mv = if direction == Orientation::Left { Move::Left } else { Move::Right };
+                events.send(BoardEvent::pill_moved(board_id, entity, mv));
+            },
+            SimEvent::PillRotated { piece, direction } => {
+                let Some(entity) = simulation.entity(piece) else { continue };
+                let rotate = if direction == Orientation::Left { Rotate::Left } else { Rotate::Right };
+                events.send(BoardEvent::pill_moved(board_id, entity, rotate));
+            },
+            SimEvent::PillLocked { piece } => {
+                let Some(entity) = simulation.entity(piece) else { continue };
+                commands.entity(entity).remove::<PivotPiece>();
+                events.send(BoardEvent::pill_locked(board_id, entity));
+            },
+            SimEvent::StacksRemoved { piece, remaining } => {
+                let Some(entity) = simulation.entity(piece) else { continue };
+                if remaining < 1 {
+                    commands.entity(entity).remove::<Stacked>();
+                } else {
+                    commands.entity(entity).insert(Stacked(remaining));
+                }
+            },
+            SimEvent::VirusRemoved { piece, color, row, col } => {
+                let Some(entity) = simulation.entity(piece) else { continue };
+                events.send(BoardEvent::virus_removed(board_id, entity, Virus(color), row as u8, col as u8));
+            },
+            SimEvent::CellsCleared { pieces, chain, groups } => {
+                for entity in pieces.iter().filter_map(|piece| simulation.entity(*piece)) {
+                    commands.entity(entity).insert(ClearedCell);
+                }
+                events.send(BoardEvent::cells_cleared(board_id, pieces.len(), chain, groups));
+            },
+            SimEvent::Exploded { pieces } => {
+                for entity in pieces.iter().filter_map(|piece| simulation.entity(*piece)) {
+                    commands.entity(entity).insert(ClearedCell);
+                }
+            },
+            SimEvent::ClearFinished { pieces } => {
+                for piece in pieces {
+                    if let Some(entity) = simulation.entities.remove(&piece) {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                }
+            },
+            SimEvent::GarbageSent { colors } => {
+                events.send(BoardEvent::garbage_sent(board_id, colors));
+            },
+            SimEvent::GarbageAdded { piece, color, row, col } => {
+                let entity = commands.spawn((
+                    Pill(color),
+                    BoardPosition { row: row as u8, column: col as u8 },
+                    InBoard(board_id),
+                    RemoveStack(0),
+                )).set_parent(board_id).id();
+                simulation.entities.insert(piece, entity);
+            },
+            SimEvent::Finished(outcome) => {
+                commands.entity(board_id).insert(match outcome {
+                    GameOutcome::Win => BoardFinished::Win,
+                    GameOutcome::Loss => BoardFinished::Loss,
+                });
+            },
+        }
+    }
+}
+
+fn sync_board(
+    simulation: &BoardSimulation,
+    board: &mut GameBoard,
+    positions: &mut Query<&mut BoardPosition>,
+) {
+    let next_board = simulation.simulation.board().map(|piece| simulation.entity(piece).unwrap_or(Entity::PLACEHOLDER));
+    if next_board == **board {
+        return;
+    }
+    **board = next_board;
+    for row in 0..board.rows {
+        for col in 0..board.cols {
+            if let Cell::Pill(pill_ent, _, maybe_orientation) = board.get(row, col) {
+                if let Ok(mut pos) = positions.get_mut(pill_ent) {
+                    // TODO: This is to handle the case where the pill is in the middle of a rotation
+                    // Since there is no orientation component, the renderer just uses the board data to get orientation
+                    // However, only one piece moves during a rotation, so the other piece will be out of sync w.r.t its rotation
+                    if maybe_orientation.is_some() {
+                        pos.row = row as u8;
+                        pos.column = col as u8;
+                    } else {
+                        pos.set_if_neq(BoardPosition { row: row as u8, column: col as u8 });
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use pills_game_board::*;
+use serde::{Deserialize, Serialize};
+use crate::*;
+
+// A board in the middle of being played, written out when the level is suspended
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BoardSnapshot {
+    // Played back to rebuild the simulation, its timers and its rng exactly
+    pub recording: Replay,
+    // What the board looked like, checked against the played back simulation
+    pub cells: Board<PieceId>,
+    pub queue: Vec<[(PieceId, CellColor); 2]>,
+    pub held: Option<[(PieceId, CellColor); 2]>,
+    // The `Stacked`, `RemoveStack` and `Explosive` values on the piece entities
+    pub traits: Vec<(PieceId, PieceTraits)>,
+    pub drop_period: f32,
+    // How far into the current drop the pill was
+    pub drop_elapsed: f32,
+    pub speed: SpeedCurve,
+    pub score: usize,
+}
+
+impl BoardSnapshot {
+    // None for boards that aren't being recorded, like replays
+    pub fn capture(
+        simulation: &BoardSimulation,
+        traits: impl Fn(Entity) -> Option<PieceTraits>,
+        drop_period: f32,
+        drop_elapsed: f32,
+        speed: SpeedCurve,
+        score: usize,
+    ) -> Option<Self> {
+        let recording = simulation.simulation.recording()?.clone();
+        let mut pieces: Vec<(PieceId, Entity)> = simulation.entities.iter().map(|(piece, entity)| (*piece, *entity)).collect();
+        pieces.sort_unstable_by_key(|(piece, _)| *piece);
+        Some(Self {
+            recording,
+            cells: simulation.simulation.board().clone(),
+            queue: simulation.simulation.queue().copied().collect(),
+            held: simulation.simulation.held_pill(),
+            traits: pieces.into_iter()
+                .filter_map(|(piece, entity)| traits(entity).map(|traits| (piece, traits)))
+                .collect(),
+            drop_period,
+            drop_elapsed,
+            speed,
+            score,
+        })
+    }
+}
+
+// Boards spawned with this pick up where the snapshot left off instead of starting over
+#[derive(Component)]
+pub struct RestoreBoard(pub BoardSnapshot);
+
+// Boards and pieces rebuilt from a snapshot already have their augments applied
+#[derive(Component)]
+pub struct Restored;
+
+// Plays the snapshot back and spawns an entity for every piece still on the board, in the queue or held.
+// None when the played back board doesn't match the snapshot
+pub(crate) fn restore_simulation(
+    commands: &mut Commands,
+    board_id: Entity,
+    snapshot: &BoardSnapshot,
+) -> Option<BoardSimulation> {
+    let mut simulation = snapshot.recording.resume();
+    for (piece, traits) in snapshot.traits.iter() {
+        simulation.set_traits(*piece, *traits);
+    }
+    let queue: Vec<[(PieceId, CellColor); 2]> = simulation.queue().copied().collect();
+    if *simulation.board() != snapshot.cells || queue != snapshot.queue || simulation.held_pill() != snapshot.held {
+        return None;
+    }
+    let mut restored = BoardSimulation { simulation, entities: HashMap::default() };
+    let traits = |piece| restored.simulation.traits(piece).copied().unwrap_or_default();
+    let pivot = restored.simulation.pivot_piece();
+    let board = restored.simulation.board();
+    let mut entities = HashMap::default();
+    for row in 0..board.rows {
+        for col in 0..board.cols {
+            let position = BoardPosition { row: row as u8, column: col as u8 };
+            let (piece, mut entity) = match board.get(row, col) {
+                Cell::Empty => continue,
+                Cell::Virus(piece, color) => (piece, commands.spawn((
+                    Virus(color),
+                    position,
+                    InBoard(board_id),
+                    Stacked(traits(piece).stacks),
+                    Explosive(traits(piece).explosive),
+                    Restored,
+                ))),
+                Cell::Pill(piece, color, _) => (piece, commands.spawn((
+                    Pill(color),
+                    position,
+                    InBoard(board_id),
+                    RemoveStack(traits(piece).remove_stacks),
+                    Restored,
+                ))),
+            };
+            if pivot == Some(piece) {
+                entity.insert(PivotPiece);
+            }
+            entities.insert(piece, entity.set_parent(board_id).id());
+        }
+    }
+    for (position, pieces) in queue.iter().enumerate() {
+        for (half, (piece, color)) in pieces.iter().enumerate() {
+            let next_pill = NextPill { position: position as u8, half: half as u8 };
+            let entity = commands.spawn((Pill(*color), next_pill, InBoard(board_id), RemoveStack(traits(*piece).remove_stacks), Restored)).id();
+            entities.insert(*piece, entity);
+        }
+    }
+    for (half, (piece, color)) in restored.simulation.held_pill().into_iter().flatten().enumerate() {
+        let held_pill = HeldPill { half: half as u8 };
+        let entity = commands.spawn((Pill(color), held_pill, InBoard(board_id), RemoveStack(traits(piece).remove_stacks), Restored)).id();
+        entities.insert(piece, entity);
+    }
+    restored.entities = entities;
+    Some(restored)
+}
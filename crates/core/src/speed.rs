@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::*;
+
+// Shortens the board's drop period as it is played, a step at a time down to a floor.
+// A step comes every `pills_per_step` pills locked and every `viruses_per_step` viruses cleared,
+// either can be zero to ignore it
+#[derive(Clone, Component, Debug, Serialize, Deserialize)]
+pub struct SpeedCurve {
+    pub pills_per_step: usize,
+    pub viruses_per_step: usize,
+    // Seconds taken off the drop period each step
+    pub step: f32,
+    pub floor: f32,
+    pills: usize,
+    viruses: usize,
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        Self::new(10, 0, 0.05, 0.15)
+    }
+}
+
+impl SpeedCurve {
+    pub fn new(pills_per_step: usize, viruses_per_step: usize, step: f32, floor: f32) -> Self {
+        Self { pills_per_step, viruses_per_step, step, floor, pills: 0, viruses: 0 }
+    }
+
+    // Steps taken so far, 0 is the board's starting speed
+    pub fn level(&self) -> usize {
+        let steps = |count: usize, per_step: usize| count.checked_div(per_step).unwrap_or(0);
+        steps(self.pills, self.pills_per_step) + steps(self.viruses, self.viruses_per_step)
+    }
+
+    // A board that starts out faster than the floor is never slowed down to it
+    pub fn drop_period(&self, base: f32) -> f32 {
+        (base - self.step * self.level() as f32).max(self.floor.min(base))
+    }
+}
+
+impl BoardConfig {
+    // The drop period the board is at now, its speed curve taken into account
+    pub fn current_drop_period(&self, speed: Option<&SpeedCurve>) -> f32 {
+        speed.map_or(self.drop_period, |speed| speed.drop_period(self.drop_period))
+    }
+}
+
+// Drops the pill a row each time it finishes, for whatever is controlling the board
+#[derive(Component, Deref, DerefMut)]
+pub struct DropTimer(Timer);
+
+impl DropTimer {
+    // A restored board carries on partway through the drop it was suspended in
+    pub fn new(config: &BoardConfig, restore: Option<&RestoreBoard>) -> Self {
+        let mut timer = Timer::from_seconds(config.drop_period, TimerMode::Repeating);
+        if let Some(restore) = restore {
+            timer.set_elapsed(Duration::from_secs_f32(restore.0.drop_elapsed));
+        }
+        Self(timer)
+    }
+
+    // Follows the speed curve without losing the time into the current drop
+    pub fn update_period(&mut self, config: &BoardConfig, speed: Option<&SpeedCurve>) {
+        let period = Duration::from_secs_f32(config.current_drop_period(speed));
+        if self.duration() != period {
+            self.set_duration(period);
+        }
+    }
+}
+
+pub(crate) fn advance_speed_curves(
+    mut events: EventReader<BoardEvent>,
+    mut curves: Query<&mut SpeedCurve>,
+) {
+    for event in events.read() {
+        match event {
+            BoardEvent::PillLocked(locked) => {
+                if let Ok(mut curve) = curves.get_mut(locked.board) {
+                    curve.pills += 1;
+                }
+            },
+            BoardEvent::VirusRemoved(removed) => {
+                if let Ok(mut curve) = curves.get_mut(removed.board) {
+                    curve.viruses += 1;
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve(pills: usize, viruses: usize) -> SpeedCurve {
+        SpeedCurve { pills, viruses, ..SpeedCurve::new(10, 4, 0.05, 0.15) }
+    }
+
+    #[test]
+    fn test_level_counts_whole_steps_of_each() {
+        assert_eq!(curve(0, 0).level(), 0);
+        assert_eq!(curve(9, 3).level(), 0);
+        assert_eq!(curve(10, 0).level(), 1);
+        assert_eq!(curve(25, 9).level(), 4);
+    }
+
+    #[test]
+    fn test_zero_per_step_is_ignored() {
+        let curve = SpeedCurve { pills: 30, viruses: 8, ..SpeedCurve::new(0, 4, 0.05, 0.15) };
+        assert_eq!(curve.level(), 2);
+        let curve = SpeedCurve { pills: 30, viruses: 8, ..SpeedCurve::new(0, 0, 0.05, 0.15) };
+        assert_eq!(curve.level(), 0);
+    }
+
+    #[test]
+    fn test_drop_period_steps_down_to_the_floor() {
+        assert_eq!(curve(0, 0).drop_period(0.8), 0.8);
+        assert!((curve(20, 0).drop_period(0.8) - 0.7).abs() < 1e-6);
+        assert_eq!(curve(1000, 0).drop_period(0.8), 0.15);
+    }
+
+    #[test]
+    fn test_base_below_the_floor_is_kept() {
+        assert_eq!(curve(0, 0).drop_period(0.1), 0.1);
+        assert_eq!(curve(1000, 0).drop_period(0.1), 0.1);
+    }
+
+    #[test]
+    fn test_current_drop_period_without_a_curve_is_the_base() {
+        let config = BoardConfig::default();
+        assert_eq!(config.current_drop_period(None), config.drop_period);
+        assert_eq!(config.current_drop_period(Some(&curve(1000, 0))), 0.15);
+    }
+}
@@ -1,5 +1,8 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CellColor {
     RED,
     BLUE,
@@ -9,7 +12,7 @@ pub enum CellColor {
     PURPLE,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Orientation {
     Above,
     Right,
@@ -17,7 +20,18 @@ pub enum Orientation {
     Left,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+impl Orientation {
+    pub fn opposite(&self) -> Self {
+        match self {
+            Orientation::Above => Orientation::Below,
+            Orientation::Below => Orientation::Above,
+            Orientation::Left => Orientation::Right,
+            Orientation::Right => Orientation::Left,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Cell<T: Clone + Copy + PartialEq> {
     Empty,
     Virus(T, CellColor),
@@ -70,7 +84,44 @@ impl<T: Clone + Copy + PartialEq> Cell<T> {
     }
 }
 
-#[derive(Clone, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchRules {
+    // Shortest straight run that clears
+    pub min_run: usize,
+    // Whether runs made up only of viruses clear
+    pub virus_only: bool,
+    pub diagonal: bool,
+    pub l_shapes: bool,
+    pub t_shapes: bool,
+    // Shortest horizontal and vertical arm of an L or T shape
+    pub shape_arm: usize,
+    pub squares: bool,
+    // Pills needed for a group containing pills to clear
+    pub min_pills: usize,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            min_run: 4,
+            virus_only: false,
+            diagonal: false,
+            l_shapes: false,
+            t_shapes: false,
+            shape_arm: 3,
+            squares: false,
+            min_pills: 1,
+        }
+    }
+}
+
+// A cell and, if it is half of a pill, the other half with its row and column
+pub type PairedCell<T> = (Cell<T>, Option<(Cell<T>, usize, usize)>);
+
+// From, to and the cell to place at to
+type CellMove<T> = ((usize, usize), (usize, usize), Cell<T>);
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Board<T: Clone + Copy + PartialEq> {
     pub rows: usize,
     pub cols: usize,
@@ -107,6 +158,45 @@ impl<T: Clone + Copy + PartialEq> std::fmt::Debug for Board<T> {
     }
 }
 
+// Groups sharing a cell are joined before they are numbered
+fn merge_overlapping(groups: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    fn find(parent: &mut [usize], mut group: usize) -> usize {
+        while parent[group] != group {
+            parent[group] = parent[parent[group]];
+            group = parent[group];
+        }
+        group
+    }
+    let mut parent: Vec<usize> = (0..groups.len()).collect();
+    let mut owners: HashMap<usize, usize> = HashMap::new();
+    for (group, indices) in groups.iter().enumerate() {
+        for index in indices {
+            match owners.get(index) {
+                Some(&owner) => {
+                    let (a, b) = (find(&mut parent, group), find(&mut parent, owner));
+                    parent[a] = b;
+                },
+                None => { owners.insert(*index, group); },
+            }
+        }
+    }
+    let mut merged: Vec<Vec<usize>> = vec![];
+    let mut slots: HashMap<usize, usize> = HashMap::new();
+    for (group, indices) in groups.into_iter().enumerate() {
+        let root = find(&mut parent, group);
+        let slot = *slots.entry(root).or_insert_with(|| {
+            merged.push(vec![]);
+            merged.len() - 1
+        });
+        for index in indices {
+            if !merged[slot].contains(&index) {
+                merged[slot].push(index);
+            }
+        }
+    }
+    merged
+}
+
 impl<T: Clone + Copy + PartialEq> Board<T> {
     
     pub fn new(rows: usize, cols: usize) -> Self {
@@ -117,70 +207,130 @@ impl<T: Clone + Copy + PartialEq> Board<T> {
         }
     }
 
+    // The same board with every cell's value swapped for another, e.g. ids for entities
+    pub fn map<U, F>(&self, f: F) -> Board<U> where
+        U: Clone + Copy + PartialEq,
+        F: Fn(T) -> U {
+        Board {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self.cells.iter().map(|cell| match *cell {
+                Cell::Empty => Cell::Empty,
+                Cell::Virus(t, color) => Cell::Virus(f(t), color),
+                Cell::Pill(t, color, o) => Cell::Pill(f(t), color, o),
+            }).collect(),
+        }
+    }
+
     pub fn resolve<F>(&self, cmp: F) -> (Self, Vec<u8>) where
+        F: Fn(Cell<T>, Cell<T>) -> bool {
+        self.resolve_with(&MatchRules::default(), cmp)
+    }
+
+    pub fn resolve_with<F>(&self, rules: &MatchRules, cmp: F) -> (Self, Vec<u8>) where
         F: Fn(Cell<T>, Cell<T>) -> bool {
         let mut new_board = self.clone();
         let mut match_mask: Vec<u8> = vec![0; new_board.cells.len()];
-        let mut mask_val = 1;
-        for row in 0..self.rows {
-            let mut matches = 1;
-            let mut pills = if self.get(row, 0).is_pill() { 1 } else { 0 };
-            for col in 1..self.cols {
-                if cmp(self.get(row, col), self.get(row, col - 1)) {
-                    matches += 1;
-                    if self.get(row, col).is_pill() { pills += 1; }
-                } else {
-                    if matches >= 4 && pills >= 1 {
-                        for i in 0..matches {
-                            let index = self.get_index(row, col-i-1);
-                            match_mask[index] = mask_val;
-                            new_board.remove_piece(row, col - i - 1);
+        for (mask_val, group) in (1..).zip(self.match_groups(rules, &cmp)) {
+            for index in group {
+                let (row, col) = self.get_row_col(index);
+                match_mask[index] = mask_val;
+                new_board.remove_piece(row, col);
+            }
+        }
+        (new_board, match_mask)
+    }
+
+    fn match_groups<F>(&self, rules: &MatchRules, cmp: &F) -> Vec<Vec<usize>> where
+        F: Fn(Cell<T>, Cell<T>) -> bool {
+        let shapes = rules.l_shapes || rules.t_shapes;
+        let shortest = if shapes { std::cmp::min(rules.min_run, rules.shape_arm) } else { rules.min_run };
+        let horizontal = self.runs((0, 1), shortest, cmp);
+        let vertical = self.runs((1, 0), shortest, cmp);
+        let mut groups: Vec<Vec<usize>> = horizontal.iter()
+            .chain(vertical.iter())
+            .filter(|run| run.len() >= rules.min_run)
+            .cloned()
+            .collect();
+        if rules.diagonal {
+            groups.extend(self.runs((1, 1), rules.min_run, cmp));
+            groups.extend(self.runs((1, -1), rules.min_run, cmp));
+        }
+        if shapes {
+            for h in horizontal.iter().filter(|run| run.len() >= rules.shape_arm) {
+                for v in vertical.iter().filter(|run| run.len() >= rules.shape_arm) {
+                    if let Some(shared) = h.iter().find(|index| v.contains(index)) {
+                        // Sharing an end of both runs is an L, anything else is a T
+                        let is_end = |run: &Vec<usize>| run.first() == Some(shared) || run.last() == Some(shared);
+                        let allowed = if is_end(h) && is_end(v) { rules.l_shapes } else { rules.t_shapes };
+                        if allowed {
+                            let mut group = h.clone();
+                            group.extend(v.iter().filter(|index| *index != shared));
+                            groups.push(group);
                         }
-                        mask_val += 1;
                     }
-                    matches = 1;
-                    pills = if self.get(row, col).is_pill() { 1 } else { 0 };
-                }
-            }
-            if matches >= 4 && pills >= 1 {
-                for i in 0..matches {
-                    let index = self.get_index(row, self.cols - i - 1);
-                    match_mask[index] = mask_val;
-                    new_board.remove_piece(row, self.cols - i - 1);
                 }
-                mask_val += 1;
             }
         }
-        for col in 0..self.cols {
-            let mut matches = 1;
-            let mut pills = if self.get(0, col).is_pill() { 1 } else { 0 };
-            for row in 1..self.rows {
-                if cmp(self.get(row, col), self.get(row - 1, col)) {
-                    matches += 1;
-                    if self.get(row, col).is_pill() { pills += 1; }
-                } else {
-                    if matches >= 4 && pills >= 1 {
-                        for i in 0..matches {
-                            let index = self.get_index(row-i-1, col);
-                            match_mask[index] = mask_val;
-                            new_board.remove_piece(row - i - 1, col);
-                        }
-                        mask_val += 1;
+        if rules.squares {
+            for row in 0..self.rows.saturating_sub(1) {
+                for col in 0..self.cols.saturating_sub(1) {
+                    let (a, b) = (self.get(row, col), self.get(row, col + 1));
+                    let (c, d) = (self.get(row + 1, col), self.get(row + 1, col + 1));
+                    if !a.is_empty() && cmp(b, a) && cmp(c, a) && cmp(d, b) && cmp(d, c) {
+                        groups.push(vec![
+                            self.get_index(row, col),
+                            self.get_index(row, col + 1),
+                            self.get_index(row + 1, col),
+                            self.get_index(row + 1, col + 1),
+                        ]);
                     }
-                    matches = 1;
-                    pills = if self.get(row, col).is_pill() { 1 } else { 0 };
                 }
             }
-            if matches >= 4 && pills >= 1 {
-                for i in 0..matches {
-                    let index = self.get_index(self.rows - i - 1, col);
-                    match_mask[index] = mask_val;
-                    new_board.remove_piece(self.rows - i - 1, col);
+        }
+        groups.retain(|group| {
+            let pills = group.iter().filter(|index| self.cells[**index].is_pill()).count();
+            if pills == 0 { rules.virus_only } else { pills >= rules.min_pills }
+        });
+        // Shapes and squares overlap the runs they are built from, so those are one clear.
+        // Plain runs crossing each other still count as separate groups
+        if shapes || rules.squares {
+            merge_overlapping(groups)
+        } else {
+            groups
+        }
+    }
+
+    // Every run of at least `min_len` non-empty matching cells walking in the direction of `step`
+    fn runs<F>(&self, step: (isize, isize), min_len: usize, cmp: &F) -> Vec<Vec<usize>> where
+        F: Fn(Cell<T>, Cell<T>) -> bool {
+        let offset = |row: usize, col: usize, sign: isize| -> Option<(usize, usize)> {
+            let r = row as isize + step.0 * sign;
+            let c = col as isize + step.1 * sign;
+            if r < 0 || c < 0 || r >= self.rows as isize || c >= self.cols as isize { return None; }
+            Some((r as usize, c as usize))
+        };
+        let mut runs = vec![];
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let cell = self.get(row, col);
+                if cell.is_empty() { continue; }
+                if let Some((r, c)) = offset(row, col, -1) {
+                    if cmp(cell, self.get(r, c)) { continue; }
+                }
+                let mut run = vec![self.get_index(row, col)];
+                let (mut r, mut c) = (row, col);
+                while let Some((next_r, next_c)) = offset(r, c, 1) {
+                    if !cmp(self.get(next_r, next_c), self.get(r, c)) { break; }
+                    run.push(self.get_index(next_r, next_c));
+                    (r, c) = (next_r, next_c);
+                }
+                if run.len() >= min_len {
+                    runs.push(run);
                 }
-                mask_val += 1;
             }
         }
-        (new_board, match_mask)
+        runs
     }
 
     pub fn next(&self) -> Self {
@@ -239,8 +389,14 @@ impl<T: Clone + Copy + PartialEq> Board<T> {
     }
 
     pub fn get(&self, row: usize, col: usize) -> Cell<T> {
-        // TODO panic if row or col is out of bounds
-        return self.cells[row * self.cols + col];
+        self.try_get(row, col).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_get(&self, row: usize, col: usize) -> Result<Cell<T>, BoardError> {
+        if row >= self.rows || col >= self.cols {
+            return Err(BoardError::OutOfBounds { row, col });
+        }
+        Ok(self.cells[row * self.cols + col])
     }
 
     pub fn get_index(&self, row: usize, col: usize) -> usize {
@@ -251,37 +407,49 @@ impl<T: Clone + Copy + PartialEq> Board<T> {
         return (index / self.cols, index % self.cols);
     }
 
-    pub fn get_paired(&self, row: usize, col: usize) -> (Cell<T>, Option<(Cell<T>, usize, usize)>) {
-        // TODO panic if row or col is out of bounds
+    // The position next to (row, col) in the direction of orientation, if it is on the board
+    pub fn neighbor(&self, row: usize, col: usize, orientation: Orientation) -> Option<(usize, usize)> {
+        match orientation {
+            Orientation::Above if row + 1 < self.rows => Some((row + 1, col)),
+            Orientation::Below if row > 0 => Some((row - 1, col)),
+            Orientation::Left if col > 0 => Some((row, col - 1)),
+            Orientation::Right if col + 1 < self.cols => Some((row, col + 1)),
+            _ => None,
+        }
+    }
+
+    pub fn get_paired(&self, row: usize, col: usize) -> PairedCell<T> {
         let cell = self.get(row, col);
-        match cell {
-            Cell::Pill(_, _, maybe_orientation) => {
-                match maybe_orientation {
-                    Some(Orientation::Right) => {
-                        return (cell, Some((self.get(row, col + 1), row, col + 1)));
-                    },
-                    Some(Orientation::Left) => {
-                        return (cell, Some((self.get(row, col - 1), row, col - 1)));
-                    },
-                    Some(Orientation::Above) => {
-                        return (cell, Some((self.get(row + 1, col), row + 1, col)));
-                    },
-                    Some(Orientation::Below) => {
-                        return (cell, Some((self.get(row - 1, col), row - 1, col)));
-                    },
-                    _ => {
-                        return (cell, None);
-                    }
-                }
-            },
-            _ => {
-                return (cell, None);
-            }
+        let paired = cell.get_orientation()
+            .and_then(|orientation| self.neighbor(row, col, orientation))
+            .map(|(row, col)| (self.get(row, col), row, col));
+        (cell, paired)
+    }
+
+    pub fn try_get_paired(&self, row: usize, col: usize) -> Result<PairedCell<T>, BoardError> {
+        let cell = self.try_get(row, col)?;
+        let Some(orientation) = cell.get_orientation() else {
+            return Ok((cell, None));
+        };
+        let (other_row, other_col) = self.neighbor(row, col, orientation)
+            .ok_or(BoardError::BrokenPairing { row, col })?;
+        let other = self.get(other_row, other_col);
+        if !other.is_pill() || other.get_orientation() != Some(orientation.opposite()) {
+            return Err(BoardError::BrokenPairing { row, col });
         }
+        Ok((cell, Some((other, other_row, other_col))))
     }
 
     pub fn set(&mut self, row: usize, col: usize, cell: Cell<T>) {
+        self.try_set(row, col, cell).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set(&mut self, row: usize, col: usize, cell: Cell<T>) -> Result<(), BoardError> {
+        if row >= self.rows || col >= self.cols {
+            return Err(BoardError::OutOfBounds { row, col });
+        }
         self.cells[row * self.cols + col] = cell;
+        Ok(())
     }
 
     fn remove_piece(&mut self, row: usize, col:usize) -> &mut Self {
@@ -298,124 +466,76 @@ impl<T: Clone + Copy + PartialEq> Board<T> {
         self
     }
 
-    fn remove_pill(&mut self, row: usize, col: usize) -> &mut Self {
-        let (_, maybe_piece) = self.get_paired(row, col);
-        self.set(row, col, Cell::Empty);
-        if let Some((_, row, col)) = maybe_piece {
-            self.set(row, col, Cell::Empty);
+    // Moves every (from, to, cell) at once, failing without changes if any destination
+    // is occupied by something other than one of the cells being moved
+    fn place(&mut self, moves: &[CellMove<T>]) -> Result<(), BoardError> {
+        for (_, to, _) in moves {
+            let vacated = moves.iter().any(|(from, _, _)| from == to);
+            if !vacated && !self.get(to.0, to.1).is_empty() {
+                return Err(BoardError::CellOccupied { row: to.0, col: to.1 });
+            }
         }
-        self
-    }
-
-    fn add_pill(&mut self, row: usize, col: usize, pill: (Cell<T>, Cell<T>)) -> bool {
-        if row >= self.rows || col >= self.cols { return false; }
-        let origin_cell = self.get(row, col);
-        if !origin_cell.is_empty() { return false; }
-        if origin_cell.is_pill() { return false; }
-        match pill.0 {
-            Cell::Pill(_, _, Some(orientation)) => {
-                let (mut other_row, mut other_col) = (row, col);
-                match orientation {
-                    Orientation::Above => { 
-                        if other_row >= self.rows - 1 { return false; }
-                        other_row += 1; 
-                    },
-                    Orientation::Below => { 
-                        if other_row <= 0 { return false; }
-                        other_row -= 1; 
-                    },
-                    Orientation::Left => { 
-                        if other_col <= 0 { return false; }
-                        other_col -= 1; 
-                    },
-                    Orientation::Right => { 
-                        if other_col >= self.cols - 1 { return false; }
-                        other_col += 1; 
-                    },
-                }
-                match self.get(other_row, other_col) {
-                    Cell::Empty => {
-                        self.set(row, col, pill.0);
-                        self.set(other_row, other_col, pill.1);
-                    },
-                    _ => { return false; },
-                }
-            },
-            _ => { return false; },
+        for (from, _, _) in moves {
+            self.set(from.0, from.1, Cell::Empty);
+        }
+        for (_, to, cell) in moves {
+            self.set(to.0, to.1, *cell);
         }
-        true
+        Ok(())
     }
 
     pub fn move_pill(&mut self, from: (usize, usize), to: (usize, usize)) -> bool {
-        let (pill, maybe_pill_info) = self.get_paired(from.0, from.1);
-        self.remove_pill(from.0, from.1);
-        if self.add_pill(to.0, to.1, (pill, maybe_pill_info.unwrap_or((Cell::Empty, 0, 0)).0)) {
-            return true
+        self.try_move_pill(from, to).is_ok()
+    }
+
+    pub fn try_move_pill(&mut self, from: (usize, usize), to: (usize, usize)) -> Result<(), BoardError> {
+        let (pill, maybe_paired) = self.try_get_paired(from.0, from.1)?;
+        if !pill.is_pill() {
+            return Err(BoardError::NotAPill { row: from.0, col: from.1 });
         }
-        self.add_pill(from.0, from.1, (pill, maybe_pill_info.unwrap_or((Cell::Empty, 0, 0)).0));
-        false
+        self.try_get(to.0, to.1)?;
+        let mut moves = vec![(from, to, pill)];
+        if let (Some(orientation), Some((paired, row, col))) = (pill.get_orientation(), maybe_paired) {
+            let paired_to = self.neighbor(to.0, to.1, orientation)
+                .ok_or(BoardError::OutOfBounds { row: to.0, col: to.1 })?;
+            moves.push(((row, col), paired_to, paired));
+        }
+        self.place(&moves)
+    }
+
+    pub fn rotate_pill(&mut self, at: (usize, usize), direction: Orientation) -> bool {
+        self.try_rotate_pill(at, direction).is_ok()
     }
 
     // Left : Above -> Left -> Below -> Right -> Above
     // Right : Above -> Right -> Below -> Left -> Above
-    pub fn rotate_pill(&mut self, at: (usize, usize), direction: Orientation) -> bool {
-        let original_pair = self.get_paired(at.0, at.1);
-        let new_pair: Option<(Cell<T>, Cell<T>)> = match original_pair {
-            (Cell::Pill(t1, c1, Some(o1)), Some((Cell::Pill(t2, c2, Some(_)), _, _))) => {
-                match (direction, o1) {
-                    (Orientation::Left, Orientation::Above) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Left));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Right));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Left, Orientation::Left) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Below));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Above));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Left, Orientation::Below) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Right));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Left));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Left, Orientation::Right) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Above));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Below));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Right, Orientation::Above) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Right));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Left));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Right, Orientation::Right) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Below));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Above));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Right, Orientation::Below) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Left));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Right));
-                        Some((p1, p2))
-                    },
-                    (Orientation::Right, Orientation::Left) => {
-                        let p1 = Cell::Pill(t1, c1, Some(Orientation::Above));
-                        let p2 = Cell::Pill(t2, c2, Some(Orientation::Below));
-                        Some((p1, p2))
-                    },
-                    _ => { None }
-                }
-            },
-            _ => { None },
+    pub fn try_rotate_pill(&mut self, at: (usize, usize), direction: Orientation) -> Result<(), BoardError> {
+        let (pill, maybe_paired) = self.try_get_paired(at.0, at.1)?;
+        let (t1, c1, o1) = match pill {
+            Cell::Pill(t, c, Some(o)) => (t, c, o),
+            Cell::Pill(_, _, None) => return Err(BoardError::Unpaired { row: at.0, col: at.1 }),
+            _ => return Err(BoardError::NotAPill { row: at.0, col: at.1 }),
         };
-        if let Some((c1, c2)) = new_pair {
-            self.remove_pill(at.0, at.1);
-            if self.add_pill(at.0, at.1, (c1, c2)) {
-                return true;
-            }
-            self.add_pill(at.0, at.1, (original_pair.0, original_pair.1.unwrap_or((Cell::Empty, 0, 0)).0));
-        }
-        false
+        let Some((Cell::Pill(t2, c2, _), row, col)) = maybe_paired else {
+            return Err(BoardError::BrokenPairing { row: at.0, col: at.1 });
+        };
+        let orientation = match (direction, o1) {
+            (Orientation::Left, Orientation::Above) => Orientation::Left,
+            (Orientation::Left, Orientation::Left) => Orientation::Below,
+            (Orientation::Left, Orientation::Below) => Orientation::Right,
+            (Orientation::Left, Orientation::Right) => Orientation::Above,
+            (Orientation::Right, Orientation::Above) => Orientation::Right,
+            (Orientation::Right, Orientation::Right) => Orientation::Below,
+            (Orientation::Right, Orientation::Below) => Orientation::Left,
+            (Orientation::Right, Orientation::Left) => Orientation::Above,
+            _ => return Err(BoardError::InvalidRotation(direction)),
+        };
+        let paired_to = self.neighbor(at.0, at.1, orientation)
+            .ok_or(BoardError::OutOfBounds { row: at.0, col: at.1 })?;
+        self.place(&[
+            (at, at, Cell::Pill(t1, c1, Some(orientation))),
+            ((row, col), paired_to, Cell::Pill(t2, c2, Some(orientation.opposite()))),
+        ])
     }
 
     pub fn virus_count(&self) -> usize {
@@ -805,4 +925,208 @@ mod tests {
                 Cell::Pill(0, CellColor::BLUE, Some(Orientation::Above)), Cell::Empty, Cell::Empty, 
                 Cell::Pill(0, CellColor::BLUE, Some(Orientation::Below)), Cell::Empty, Cell::Empty]);
     }
+    fn same_color(a: Cell<()>, b: Cell<()>) -> bool {
+        a.color().is_some() && a.color() == b.color()
+    }
+
+    #[test]
+    fn test_shorter_min_run_resolves_three_in_a_row() {
+        let board: Board<()> = "
+            _ _ _ _
+            r R R b
+        ".parse().unwrap();
+        let rules = MatchRules { min_run: 3, ..MatchRules::default() };
+        let (next_board, mask) = board.resolve_with(&rules, same_color);
+        assert_eq!(mask, vec![1, 1, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(next_board, "
+            _ _ _ _
+            _ _ _ b
+        ".parse().unwrap());
+        let (next_board, _) = board.resolve(same_color);
+        assert_eq!(next_board, board);
+    }
+
+    #[test]
+    fn test_virus_only_runs_resolve_when_enabled() {
+        let board: Board<()> = "y y y y".parse().unwrap();
+        let rules = MatchRules { virus_only: true, ..MatchRules::default() };
+        let (next_board, mask) = board.resolve_with(&rules, same_color);
+        assert_eq!(mask, vec![1, 1, 1, 1]);
+        assert_eq!(next_board, "_ _ _ _".parse().unwrap());
+    }
+
+    #[test]
+    fn test_diagonal_runs_resolve_when_enabled() {
+        let board: Board<()> = "
+            _ _ _ B
+            _ _ b _
+            _ b _ R
+            b _ r y
+        ".parse().unwrap();
+        let (next_board, _) = board.resolve(same_color);
+        assert_eq!(next_board, board);
+        let rules = MatchRules { diagonal: true, ..MatchRules::default() };
+        let (next_board, _) = board.resolve_with(&rules, same_color);
+        assert_eq!(next_board, "
+            _ _ _ _
+            _ _ _ _
+            _ _ _ R
+            _ _ r y
+        ".parse().unwrap());
+    }
+
+    #[test]
+    fn test_l_and_t_shapes_resolve_when_enabled() {
+        let l_shape: Board<()> = "
+            Y _ _
+            y _ _
+            y Y y
+        ".parse().unwrap();
+        let t_shape: Board<()> = "
+            Y Y y
+            _ y _
+            _ y _
+        ".parse().unwrap();
+        let rules = MatchRules { l_shapes: true, ..MatchRules::default() };
+        assert_eq!(l_shape.resolve_with(&rules, same_color).0, "_ _ _\n_ _ _\n_ _ _".parse().unwrap());
+        assert_eq!(t_shape.resolve_with(&rules, same_color).0, t_shape);
+        let rules = MatchRules { t_shapes: true, ..MatchRules::default() };
+        assert_eq!(l_shape.resolve_with(&rules, same_color).0, l_shape);
+        assert_eq!(t_shape.resolve_with(&rules, same_color).0, "_ _ _\n_ _ _\n_ _ _".parse().unwrap());
+    }
+
+    #[test]
+    fn test_squares_resolve_when_enabled() {
+        let board: Board<()> = "
+            Bv b _
+            B^ b R
+        ".parse().unwrap();
+        let rules = MatchRules { squares: true, ..MatchRules::default() };
+        let (next_board, mask) = board.resolve_with(&rules, same_color);
+        assert_eq!(mask, vec![1, 1, 0, 1, 1, 0]);
+        assert_eq!(next_board, "
+            _ _ _
+            _ _ R
+        ".parse().unwrap());
+    }
+
+    #[test]
+    fn test_overlapping_groups_share_a_mask_value() {
+        let board: Board<()> = "
+            B> B< B> B<
+            B> B< B> B<
+        ".parse().unwrap();
+        let rules = MatchRules { squares: true, ..MatchRules::default() };
+        let (next_board, mask) = board.resolve_with(&rules, same_color);
+        assert_eq!(mask, vec![1; 8]);
+        assert_eq!(next_board, "_ _ _ _\n_ _ _ _".parse().unwrap());
+    }
+
+    #[test]
+    fn test_crossing_runs_are_separate_groups_by_default() {
+        let board: Board<()> = "
+            _ R _ _
+            R R R R
+            _ R _ _
+            _ R _ _
+        ".parse().unwrap();
+        let (next_board, mask) = board.resolve(same_color);
+        let mut groups: Vec<u8> = mask.into_iter().filter(|value| *value > 0).collect();
+        groups.sort_unstable();
+        groups.dedup();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(next_board, Board::new(4, 4));
+    }
+
+    #[test]
+    fn test_min_pills_rejects_groups_with_too_few_pills() {
+        let board: Board<()> = "
+            R> R< r r
+            _  _  _ R
+        ".parse().unwrap();
+        let rules = MatchRules { min_pills: 3, ..MatchRules::default() };
+        let (next_board, _) = board.resolve_with(&rules, same_color);
+        assert_eq!(next_board, board);
+        let rules = MatchRules { min_pills: 2, ..MatchRules::default() };
+        let (next_board, _) = board.resolve_with(&rules, same_color);
+        assert_eq!(next_board, "
+            _ _ _ _
+            _ _ _ R
+        ".parse().unwrap());
+    }
+    #[test]
+    fn test_checked_access_out_of_bounds() {
+        let mut board: Board<()> = Board::new(2, 3);
+        assert_eq!(board.try_get(2, 0), Err(BoardError::OutOfBounds { row: 2, col: 0 }));
+        assert_eq!(board.try_get(0, 3), Err(BoardError::OutOfBounds { row: 0, col: 3 }));
+        assert_eq!(board.try_set(0, 3, Cell::Virus((), CellColor::RED)), Err(BoardError::OutOfBounds { row: 0, col: 3 }));
+        assert_eq!(board.try_set(1, 2, Cell::Virus((), CellColor::RED)), Ok(()));
+        assert_eq!(board.try_get(1, 2), Ok(Cell::Virus((), CellColor::RED)));
+    }
+
+    #[test]
+    #[should_panic(expected = "row 0, col 3 is out of bounds")]
+    fn test_get_panics_out_of_bounds() {
+        let board: Board<()> = Board::new(2, 3);
+        board.get(0, 3);
+    }
+
+    #[test]
+    fn test_get_paired_with_broken_edge_pairing() {
+        let mut board: Board<()> = Board::new(2, 2);
+        board.set(0, 0, Cell::Pill((), CellColor::RED, Some(Orientation::Left)));
+        board.set(1, 1, Cell::Pill((), CellColor::RED, Some(Orientation::Above)));
+        assert_eq!(board.get_paired(0, 0), (Cell::Pill((), CellColor::RED, Some(Orientation::Left)), None));
+        assert_eq!(board.try_get_paired(0, 0), Err(BoardError::BrokenPairing { row: 0, col: 0 }));
+        assert_eq!(board.try_get_paired(1, 1), Err(BoardError::BrokenPairing { row: 1, col: 1 }));
+        board.set(0, 1, Cell::Pill((), CellColor::BLUE, Some(Orientation::Above)));
+        assert_eq!(board.try_get_paired(0, 1), Err(BoardError::BrokenPairing { row: 0, col: 1 }));
+    }
+
+    #[test]
+    fn test_try_move_pill_errors_leave_board_unchanged() {
+        let mut board: Board<()> = "
+            _  _  _
+            y  _  _
+            _  B> R<
+        ".parse().unwrap();
+        let original = board.clone();
+        assert_eq!(board.try_move_pill((1, 0), (1, 1)), Err(BoardError::NotAPill { row: 1, col: 0 }));
+        assert_eq!(board.try_move_pill((0, 0), (1, 1)), Err(BoardError::NotAPill { row: 0, col: 0 }));
+        assert_eq!(board.try_move_pill((0, 1), (1, 0)), Err(BoardError::CellOccupied { row: 1, col: 0 }));
+        assert_eq!(board.try_move_pill((0, 1), (0, 2)), Err(BoardError::OutOfBounds { row: 0, col: 2 }));
+        assert_eq!(board.try_move_pill((0, 1), (5, 1)), Err(BoardError::OutOfBounds { row: 5, col: 1 }));
+        assert_eq!(board.try_move_pill((9, 9), (0, 0)), Err(BoardError::OutOfBounds { row: 9, col: 9 }));
+        assert_eq!(board, original);
+        assert_eq!(board.try_move_pill((0, 2), (2, 1)), Ok(()));
+        assert_eq!(board, "
+            B> R< _
+            y  _  _
+            _  _  _
+        ".parse().unwrap());
+    }
+
+    #[test]
+    fn test_try_rotate_pill_errors() {
+        let mut board: Board<()> = "
+            _  _  _
+            B  Yv _
+            r  Y^ _
+        ".parse().unwrap();
+        let original = board.clone();
+        assert_eq!(board.try_rotate_pill((1, 0), Orientation::Left), Err(BoardError::Unpaired { row: 1, col: 0 }));
+        assert_eq!(board.try_rotate_pill((0, 0), Orientation::Left), Err(BoardError::NotAPill { row: 0, col: 0 }));
+        assert_eq!(board.try_rotate_pill((0, 1), Orientation::Above), Err(BoardError::InvalidRotation(Orientation::Above)));
+        assert_eq!(board.try_rotate_pill((0, 1), Orientation::Left), Err(BoardError::CellOccupied { row: 0, col: 0 }));
+        assert_eq!(board, original);
+        assert_eq!(board.try_rotate_pill((0, 1), Orientation::Right), Ok(()));
+        assert_eq!(board, "
+            _  _  _
+            B  _  _
+            r  Y> Y<
+        ".parse().unwrap());
+
+        let mut board: Board<()> = "Bv\nB^".parse().unwrap();
+        assert_eq!(board.try_rotate_pill((0, 0), Orientation::Right), Err(BoardError::OutOfBounds { row: 0, col: 0 }));
+    }
 }
\ No newline at end of file
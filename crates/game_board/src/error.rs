@@ -0,0 +1,30 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoardError {
+    // The position, or the other half of the pill anchored at it, is off the board
+    OutOfBounds { row: usize, col: usize },
+    CellOccupied { row: usize, col: usize },
+    NotAPill { row: usize, col: usize },
+    // The pill's orientation does not point at a half that points back at it
+    BrokenPairing { row: usize, col: usize },
+    // The pill is a single half so it cannot be rotated
+    Unpaired { row: usize, col: usize },
+    // Pills only rotate Left or Right
+    InvalidRotation(Orientation),
+}
+
+impl std::fmt::Display for BoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BoardError::OutOfBounds { row, col } => write!(f, "row {}, col {} is out of bounds", row, col),
+            BoardError::CellOccupied { row, col } => write!(f, "cell at row {}, col {} is occupied", row, col),
+            BoardError::NotAPill { row, col } => write!(f, "cell at row {}, col {} is not a pill", row, col),
+            BoardError::BrokenPairing { row, col } => write!(f, "pill at row {}, col {} has a broken pairing", row, col),
+            BoardError::Unpaired { row, col } => write!(f, "pill at row {}, col {} is not paired", row, col),
+            BoardError::InvalidRotation(direction) => write!(f, "pills cannot rotate {:?}", direction),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
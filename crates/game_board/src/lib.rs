@@ -1,3 +1,15 @@
 pub use board::*;
+pub use error::*;
+pub use movement::*;
+pub use notation::*;
+pub use replay::*;
+pub use settle::*;
+pub use simulation::*;
 
-mod board;
\ No newline at end of file
+mod board;
+mod error;
+mod movement;
+mod notation;
+mod replay;
+mod settle;
+mod simulation;
@@ -0,0 +1,281 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+// Everything applied to the pivot half of the active pill in a single tick.
+// `shift` and `rotate` only use Orientation::Left and Orientation::Right.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PillInput {
+    pub shift: Option<Orientation>,
+    pub rotate: Option<Orientation>,
+    pub drop: bool,
+    // Drops the pill as far as it goes and locks it straight away
+    #[serde(default)]
+    pub hard_drop: bool,
+    // Swaps the active pill with the held one, once per lock
+    #[serde(default)]
+    pub hold: bool,
+}
+
+impl PillInput {
+    pub fn shift(direction: Orientation) -> Self {
+        Self { shift: Some(direction), ..Self::default() }
+    }
+
+    pub fn rotate(direction: Orientation) -> Self {
+        Self { rotate: Some(direction), ..Self::default() }
+    }
+
+    pub fn drop() -> Self {
+        Self { drop: true, ..Self::default() }
+    }
+
+    pub fn hard_drop() -> Self {
+        Self { hard_drop: true, ..Self::default() }
+    }
+
+    pub fn hold() -> Self {
+        Self { hold: true, ..Self::default() }
+    }
+
+    // Every non-empty combination of inputs that can land in the same tick
+    pub fn all() -> impl Iterator<Item = PillInput> {
+        let directions = [None, Some(Orientation::Left), Some(Orientation::Right)];
+        directions.into_iter()
+            .flat_map(move |shift| directions.into_iter().map(move |rotate| (shift, rotate)))
+            .flat_map(|(shift, rotate)| [false, true].into_iter().map(move |drop| PillInput { shift, rotate, drop, ..PillInput::default() }))
+            .filter(|input| *input != PillInput::default())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PillInputResult {
+    pub pivot: (usize, usize),
+    pub moved: bool,
+    pub rotated: bool,
+    // A drop that could not move the pill down locks it in place
+    pub locked: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement<T: Clone + Copy + PartialEq> {
+    pub pivot: (usize, usize),
+    pub orientation: Orientation,
+    pub board: Board<T>,
+    // Shortest sequence of inputs from the starting position, ending with the drop that locks the pill
+    pub inputs: Vec<PillInput>,
+}
+
+impl<T: Clone + Copy + PartialEq> Board<T> {
+    pub fn apply_pill_input(&mut self, pivot: (usize, usize), input: PillInput) -> PillInputResult {
+        let (r1, c1) = pivot;
+        let (mut r2, mut c2) = (r1, c1);
+        let mut moved = false;
+        let mut rotated = false;
+        if let Cell::Pill(_, _, maybe_o) = self.get(r1, c1) {
+            match input.shift {
+                Some(Orientation::Left) => {
+                    let offset = if maybe_o == Some(Orientation::Left) { 1 } else { 0 };
+                    if c1 > offset { c2 -= 1; }
+                },
+                Some(Orientation::Right) => {
+                    let offset = if maybe_o == Some(Orientation::Right) { self.cols - 2 } else { self.cols - 1 };
+                    if c1 < offset { c2 += 1; }
+                },
+                _ => {},
+            }
+            if input.drop {
+                let offset = if maybe_o == Some(Orientation::Below) { 1 } else { 0 };
+                if r1 > offset { r2 -= 1; }
+            }
+        }
+
+        if (r1, c1) != (r2, c2) {
+            // Move without any rotation
+            moved = self.move_pill((r1, c1), (r2, c2));
+
+            // Move after rotating
+            if let (false, Some(direction)) = (moved, input.rotate) {
+                rotated = self.rotate_pill((r1, c1), direction);
+                moved = rotated && self.move_pill((r1, c1), (r2, c2));
+            }
+
+            // Rotate after move if possible
+            if let (true, false, Some(direction)) = (moved, rotated, input.rotate) {
+                rotated = self.rotate_pill((r2, c2), direction);
+            }
+        } else if let Some(direction) = input.rotate {
+            rotated = self.rotate_pill((r1, c1), direction);
+        }
+
+        PillInputResult {
+            pivot: if moved { (r2, c2) } else { (r1, c1) },
+            moved,
+            rotated,
+            locked: input.drop && !moved,
+        }
+    }
+
+    // Every distinct resting place for the pill whose pivot half is at `pivot`, found by
+    // a breadth first search over the inputs the player can give it
+    pub fn placements(&self, pivot: (usize, usize)) -> Result<Vec<Placement<T>>, BoardError> {
+        let orientation = match self.try_get_paired(pivot.0, pivot.1)? {
+            (Cell::Pill(_, _, Some(orientation)), Some(_)) => orientation,
+            (Cell::Pill(_, _, _), _) => return Err(BoardError::Unpaired { row: pivot.0, col: pivot.1 }),
+            _ => return Err(BoardError::NotAPill { row: pivot.0, col: pivot.1 }),
+        };
+        let mut visited = HashSet::from([(pivot, orientation)]);
+        let mut resting = HashSet::new();
+        let mut placements = vec![];
+        let mut queue = VecDeque::from([(self.clone(), pivot, vec![])]);
+        while let Some((board, pivot, inputs)) = queue.pop_front() {
+            for input in PillInput::all() {
+                let mut next_board = board.clone();
+                let result = next_board.apply_pill_input(pivot, input);
+                if !result.locked && !result.moved && !result.rotated {
+                    continue;
+                }
+                let orientation = next_board.get(result.pivot.0, result.pivot.1).get_orientation().unwrap();
+                let mut next_inputs = inputs.clone();
+                next_inputs.push(input);
+                if result.locked {
+                    if resting.insert(next_board.pill_footprint(result.pivot, orientation)) {
+                        placements.push(Placement { pivot: result.pivot, orientation, board: next_board, inputs: next_inputs });
+                    }
+                } else if visited.insert((result.pivot, orientation)) {
+                    queue.push_back((next_board, result.pivot, next_inputs));
+                }
+            }
+        }
+        Ok(placements)
+    }
+
+    // Where the pivot and its other half end up if the pill only drops from here
+    pub fn landing(&self, pivot: (usize, usize)) -> Result<[(usize, usize); 2], BoardError> {
+        match self.try_get_paired(pivot.0, pivot.1)? {
+            (Cell::Pill(_, _, Some(_)), Some(_)) => {},
+            (Cell::Pill(_, _, _), _) => return Err(BoardError::Unpaired { row: pivot.0, col: pivot.1 }),
+            _ => return Err(BoardError::NotAPill { row: pivot.0, col: pivot.1 }),
+        }
+        let mut board = self.clone();
+        let mut pivot = pivot;
+        loop {
+            let result = board.apply_pill_input(pivot, PillInput::drop());
+            if result.locked {
+                break;
+            }
+            pivot = result.pivot;
+        }
+        let (_, Some((_, row, col))) = board.get_paired(pivot.0, pivot.1) else { unreachable!() };
+        Ok([pivot, (row, col)])
+    }
+
+    // The cells and colors covered by a pill, so placements that look the same are only counted once
+    fn pill_footprint(&self, pivot: (usize, usize), orientation: Orientation) -> [(usize, usize, Option<CellColor>); 2] {
+        let (row, col) = self.neighbor(pivot.0, pivot.1, orientation).unwrap();
+        let mut footprint = [
+            (pivot.0, pivot.1, self.get(pivot.0, pivot.1).color()),
+            (row, col, self.get(row, col).color()),
+        ];
+        footprint.sort_by_key(|(row, col, _)| (*row, *col));
+        footprint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_pill_input_rotates_then_moves_when_blocked() {
+        let mut board: Board<()> = "
+            _ _  _
+            y B> R<
+        ".parse().unwrap();
+        let result = board.apply_pill_input((0, 2), PillInput::shift(Orientation::Left));
+        assert_eq!(result, PillInputResult { pivot: (0, 2), moved: false, rotated: false, locked: false });
+
+        let input = PillInput { shift: Some(Orientation::Left), rotate: Some(Orientation::Right), drop: false, ..PillInput::default() };
+        let result = board.apply_pill_input((0, 2), input);
+        assert_eq!(result, PillInputResult { pivot: (0, 1), moved: true, rotated: true, locked: false });
+        assert_eq!(board, "
+            _ Bv _
+            y R^ _
+        ".parse().unwrap());
+
+        let result = board.apply_pill_input((0, 1), PillInput::drop());
+        assert!(result.locked);
+    }
+
+    #[test]
+    fn test_placements_on_empty_board() {
+        let board: Board<()> = "
+            _ B> R< _
+            _ _  _  _
+            _ _  _  _
+            _ _  _  _
+        ".parse().unwrap();
+        let placements = board.placements((3, 2)).unwrap();
+        // Three horizontal and four vertical spots on the floor, each with two color orders
+        let grounded = placements.iter().filter(|p| p.board.get(0, p.pivot.1) != Cell::Empty).count();
+        assert_eq!(grounded, 14);
+        // A rotate on the same tick as a drop that cannot move locks the pill where it is, as it does in game
+        assert_eq!(placements.len(), 20);
+        for placement in placements.iter() {
+            let mut replay = board.clone();
+            let mut pivot = (3, 2);
+            for input in placement.inputs.iter() {
+                pivot = replay.apply_pill_input(pivot, *input).pivot;
+            }
+            assert_eq!(replay, placement.board);
+            assert_eq!(pivot, placement.pivot);
+        }
+        let straight_down = placements.iter().find(|p| p.pivot == (0, 2) && p.orientation == Orientation::Left).unwrap();
+        assert_eq!(straight_down.inputs.len(), 4);
+    }
+
+    #[test]
+    fn test_placements_reach_under_overhang() {
+        let board: Board<()> = "
+            _ B> R< _
+            _ _  _  _
+            _ _  _  _
+            y y  _  _
+            _ _  _  _
+        ".parse().unwrap();
+        let placements = board.placements((4, 2)).unwrap();
+        let tucked = placements.iter().find(|p| p.pivot == (0, 1) && p.orientation == Orientation::Left).unwrap();
+        assert_eq!(tucked.board, "
+            _  _  _ _
+            _  _  _ _
+            _  _  _ _
+            y  y  _ _
+            B> R< _ _
+        ".parse().unwrap());
+        // Dropped upright past the overhang, then slid under it lying flat
+        assert_eq!(tucked.inputs.len(), 6);
+        assert!(tucked.inputs.iter().any(|input| input.shift == Some(Orientation::Left) && !input.drop));
+        assert_eq!(tucked.inputs.last(), Some(&PillInput::drop()));
+    }
+
+    #[test]
+    fn test_placements_requires_paired_pill() {
+        let board: Board<()> = "r B".parse().unwrap();
+        assert_eq!(board.placements((0, 0)), Err(BoardError::NotAPill { row: 0, col: 0 }));
+        assert_eq!(board.placements((0, 1)), Err(BoardError::Unpaired { row: 0, col: 1 }));
+    }
+
+    #[test]
+    fn test_landing_stops_on_the_first_thing_below() {
+        let board: Board<()> = "
+            _ Bv _
+            _ R^ _
+            _ _  _
+            _ _  y
+            _ y  _
+        ".parse().unwrap();
+        assert_eq!(board.landing((4, 1)), Ok([(2, 1), (1, 1)]));
+        assert_eq!(board.landing((3, 1)), Ok([(1, 1), (2, 1)]));
+        assert_eq!(board.landing((0, 1)), Err(BoardError::NotAPill { row: 0, col: 1 }));
+    }
+}
@@ -0,0 +1,280 @@
+//! Text notation for boards.
+//!
+//! A board is written one line per row, top row first, which is the same
+//! order the `Debug` impl prints in. Cells within a row are separated by
+//! whitespace and each cell is one of:
+//!
+//! | Token              | Cell                                          |
+//! |--------------------|-----------------------------------------------|
+//! | `_`                | `Cell::Empty`                                 |
+//! | `r b y g o p`      | `Cell::Virus` (RED, BLUE, YELLOW, GREEN, ORANGE, PURPLE) |
+//! | `R B Y G O P`      | `Cell::Pill` with no paired half              |
+//! | `R^ Rv R< R>`      | `Cell::Pill` paired with the half above, below, to the left or to the right |
+//!
+//! Pairings must be mutual, so a horizontal pill is written `R> B<` and a
+//! vertical pill is written as `Yv` on one line with `Y^` on the line below.
+//! Blank lines and leading indentation are ignored so fixtures can be written
+//! as indented multi-line strings:
+//!
+//! ```
+//! use pills_game_board::*;
+//!
+//! let board: Board<()> = "
+//!     _  Yv _
+//!     _  Y^ _
+//!     r  B> R<
+//! ".parse().unwrap();
+//! assert_eq!(board.get(0, 0), Cell::Virus((), CellColor::RED));
+//! assert_eq!(board.get(2, 1), Cell::Pill((), CellColor::YELLOW, Some(Orientation::Below)));
+//! assert_eq!(board.to_string().parse::<Board<()>>().unwrap(), board);
+//! ```
+use super::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseBoardErrorKind {
+    Empty,
+    InvalidCell(String),
+    RowLength { expected: usize, found: usize },
+    BrokenPairing(Orientation),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseBoardError {
+    pub row: usize,
+    pub col: usize,
+    pub kind: ParseBoardErrorKind,
+}
+
+impl std::fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ParseBoardErrorKind::Empty => write!(f, "board has no rows"),
+            ParseBoardErrorKind::InvalidCell(token) => {
+                write!(f, "invalid cell `{}` at row {}, col {}", token, self.row, self.col)
+            },
+            ParseBoardErrorKind::RowLength { expected, found } => {
+                write!(f, "row {} has {} cells, expected {}", self.row, found, expected)
+            },
+            ParseBoardErrorKind::BrokenPairing(orientation) => {
+                write!(f, "pill at row {}, col {} is paired {:?} but the other half does not pair back", self.row, self.col, orientation)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ParseBoardError {}
+
+fn color_from_char(c: char) -> Option<CellColor> {
+    match c.to_ascii_uppercase() {
+        'R' => Some(CellColor::RED),
+        'B' => Some(CellColor::BLUE),
+        'Y' => Some(CellColor::YELLOW),
+        'G' => Some(CellColor::GREEN),
+        'O' => Some(CellColor::ORANGE),
+        'P' => Some(CellColor::PURPLE),
+        _ => None,
+    }
+}
+
+fn color_to_char(color: CellColor) -> char {
+    match color {
+        CellColor::RED => 'R',
+        CellColor::BLUE => 'B',
+        CellColor::YELLOW => 'Y',
+        CellColor::GREEN => 'G',
+        CellColor::ORANGE => 'O',
+        CellColor::PURPLE => 'P',
+    }
+}
+
+fn orientation_from_char(c: char) -> Option<Orientation> {
+    match c {
+        '^' => Some(Orientation::Above),
+        'v' => Some(Orientation::Below),
+        '<' => Some(Orientation::Left),
+        '>' => Some(Orientation::Right),
+        _ => None,
+    }
+}
+
+fn orientation_to_char(orientation: Orientation) -> char {
+    match orientation {
+        Orientation::Above => '^',
+        Orientation::Below => 'v',
+        Orientation::Left => '<',
+        Orientation::Right => '>',
+    }
+}
+
+fn parse_cell(token: &str) -> Option<Cell<()>> {
+    let mut chars = token.chars();
+    let first = chars.next()?;
+    let second = chars.next();
+    if chars.next().is_some() { return None; }
+    if first == '_' {
+        return if second.is_none() { Some(Cell::Empty) } else { None };
+    }
+    let color = color_from_char(first)?;
+    match (first.is_ascii_lowercase(), second) {
+        (true, None) => Some(Cell::Virus((), color)),
+        (false, None) => Some(Cell::Pill((), color, None)),
+        (false, Some(o)) => Some(Cell::Pill((), color, Some(orientation_from_char(o)?))),
+        (true, Some(_)) => None,
+    }
+}
+
+impl<T: Clone + Copy + PartialEq> Board<T> {
+    /// Parses a board written in the text notation, calling `id` with the
+    /// row and column of every non-empty cell to produce its value.
+    pub fn parse_with<F>(s: &str, mut id: F) -> Result<Self, ParseBoardError> where
+        F: FnMut(usize, usize) -> T {
+        let lines: Vec<&str> = s.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        if lines.is_empty() {
+            return Err(ParseBoardError { row: 0, col: 0, kind: ParseBoardErrorKind::Empty });
+        }
+        let rows = lines.len();
+        let cols = lines[0].split_whitespace().count();
+        let mut board = Board::new(rows, cols);
+        for (line_index, line) in lines.iter().enumerate() {
+            let row = rows - line_index - 1;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() != cols {
+                return Err(ParseBoardError {
+                    row,
+                    col: std::cmp::min(tokens.len(), cols),
+                    kind: ParseBoardErrorKind::RowLength { expected: cols, found: tokens.len() },
+                });
+            }
+            for (col, token) in tokens.iter().enumerate() {
+                let cell = match parse_cell(token) {
+                    Some(Cell::Empty) => Cell::Empty,
+                    Some(Cell::Virus(_, color)) => Cell::Virus(id(row, col), color),
+                    Some(Cell::Pill(_, color, o)) => Cell::Pill(id(row, col), color, o),
+                    None => return Err(ParseBoardError {
+                        row,
+                        col,
+                        kind: ParseBoardErrorKind::InvalidCell(token.to_string()),
+                    }),
+                };
+                board.set(row, col, cell);
+            }
+        }
+        board.check_pairings()?;
+        Ok(board)
+    }
+
+    fn check_pairings(&self) -> Result<(), ParseBoardError> {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if let Err(BoardError::BrokenPairing { row, col }) = self.try_get_paired(row, col) {
+                    let orientation = self.get(row, col).get_orientation().unwrap();
+                    return Err(ParseBoardError { row, col, kind: ParseBoardErrorKind::BrokenPairing(orientation) });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + Copy + PartialEq + Default> std::str::FromStr for Board<T> {
+    type Err = ParseBoardError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Board::parse_with(s, |_, _| T::default())
+    }
+}
+
+impl<T: Clone + Copy + PartialEq> std::fmt::Display for Board<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in (0..self.rows).rev() {
+            let tokens: Vec<String> = (0..self.cols).map(|col| match self.get(row, col) {
+                Cell::Empty => "_".to_string(),
+                Cell::Virus(_, color) => color_to_char(color).to_ascii_lowercase().to_string(),
+                Cell::Pill(_, color, None) => color_to_char(color).to_string(),
+                Cell::Pill(_, color, Some(o)) => format!("{}{}", color_to_char(color), orientation_to_char(o)),
+            }).collect();
+            writeln!(f, "{}", tokens.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_all_cell_kinds() {
+        let board: Board<()> = "
+            Yv _  g
+            Y^ R> B<
+        ".parse().unwrap();
+        assert_eq!(board.rows, 2);
+        assert_eq!(board.cols, 3);
+        assert_eq!(
+            board.cells,
+            vec![
+                Cell::Pill((), CellColor::YELLOW, Some(Orientation::Above)),
+                Cell::Pill((), CellColor::RED, Some(Orientation::Right)),
+                Cell::Pill((), CellColor::BLUE, Some(Orientation::Left)),
+                Cell::Pill((), CellColor::YELLOW, Some(Orientation::Below)),
+                Cell::Empty,
+                Cell::Virus((), CellColor::GREEN)]);
+    }
+
+    #[test]
+    fn test_parse_fails_on_broken_pairing() {
+        let error = "
+            _  R
+            B> b
+        ".parse::<Board<()>>().unwrap_err();
+        assert_eq!(error, ParseBoardError { row: 0, col: 0, kind: ParseBoardErrorKind::BrokenPairing(Orientation::Right) });
+
+        let error = "R<".parse::<Board<()>>().unwrap_err();
+        assert_eq!(error, ParseBoardError { row: 0, col: 0, kind: ParseBoardErrorKind::BrokenPairing(Orientation::Left) });
+    }
+
+    #[test]
+    fn test_parse_reports_position_of_invalid_cell() {
+        let error = "
+            _ _ _
+            _ x _
+            _ _ _
+        ".parse::<Board<()>>().unwrap_err();
+        assert_eq!(error, ParseBoardError { row: 1, col: 1, kind: ParseBoardErrorKind::InvalidCell("x".to_string()) });
+        assert_eq!(error.to_string(), "invalid cell `x` at row 1, col 1");
+        assert!("r^".parse::<Board<()>>().is_err());
+        assert!("".parse::<Board<()>>().is_err());
+    }
+
+    #[test]
+    fn test_parse_fails_on_ragged_rows() {
+        let error = "
+            _ _ _
+            _ _
+        ".parse::<Board<()>>().unwrap_err();
+        assert_eq!(error.kind, ParseBoardErrorKind::RowLength { expected: 3, found: 2 });
+        assert_eq!(error.row, 0);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let text = "Ov _ p\nO^ G> B<\nr P Y\n";
+        let board: Board<()> = text.parse().unwrap();
+        assert_eq!(board.to_string(), text);
+    }
+
+    #[test]
+    fn test_parse_with_assigns_ids() {
+        let board = Board::parse_with("
+            _ R
+            y B
+        ", |row, col| (row * 10 + col) as u32).unwrap();
+        assert_eq!(board.get(1, 1), Cell::Pill(11, CellColor::RED, None));
+        assert_eq!(board.get(0, 0), Cell::Virus(0, CellColor::YELLOW));
+        assert_eq!(board.get(0, 1), Cell::Pill(1, CellColor::BLUE, None));
+    }
+}
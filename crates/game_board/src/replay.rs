@@ -0,0 +1,125 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+// Everything fed into a simulation in one call to `step`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTick {
+    pub dt: f32,
+    #[serde(default, skip_serializing_if = "is_idle")]
+    pub input: PillInput,
+    // Traits changed on pieces since the previous tick, applied before stepping
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traits: Vec<(PieceId, PieceTraits)>,
+    // Garbage received from other boards since the previous tick
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub garbage: Vec<CellColor>,
+}
+
+fn is_idle(input: &PillInput) -> bool {
+    *input == PillInput::default()
+}
+
+// A recorded game that plays out exactly the same way from its seed and config
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub config: SimulationConfig,
+    pub ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    pub fn new(config: SimulationConfig, seed: u64) -> Self {
+        Self { seed, config, ticks: vec![] }
+    }
+
+    // A started simulation the ticks can be fed into one at a time with `play_tick`
+    pub fn start(&self) -> (GameSimulation, Vec<SimEvent>) {
+        let mut simulation = GameSimulation::new(self.config.clone(), self.seed);
+        let events = simulation.start();
+        (simulation, events)
+    }
+
+    pub fn play_tick(simulation: &mut GameSimulation, tick: &ReplayTick) -> Vec<SimEvent> {
+        for (piece, traits) in tick.traits.iter() {
+            simulation.set_traits(*piece, *traits);
+        }
+        simulation.receive_garbage(&tick.garbage);
+        simulation.step(tick.input, tick.dt)
+    }
+
+    // Plays the whole recording into a simulation that keeps recording, so a suspended game can carry on
+    pub fn resume(&self) -> GameSimulation {
+        let mut simulation = GameSimulation::recorded(self.config.clone(), self.seed);
+        simulation.start();
+        for tick in self.ticks.iter() {
+            Self::play_tick(&mut simulation, tick);
+        }
+        simulation
+    }
+
+    // Runs the whole recording without any timing, returning the final simulation and everything that happened
+    pub fn play(&self) -> (GameSimulation, Vec<SimEvent>) {
+        let (mut simulation, mut events) = self.start();
+        for tick in self.ticks.iter() {
+            events.extend(Self::play_tick(&mut simulation, tick));
+        }
+        (simulation, events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_plays_the_recorded_game() {
+        let config = SimulationConfig { max_viruses: 20, ..SimulationConfig::default() };
+        let mut simulation = GameSimulation::recorded(config, 11);
+        let mut events = simulation.start();
+        for tick in 0..600 {
+            if tick == 50 {
+                let virus = simulation.board().cells.iter().find_map(|cell| match cell {
+                    Cell::Virus(piece, _) => Some(*piece),
+                    _ => None,
+                }).unwrap();
+                simulation.set_traits(virus, PieceTraits { stacks: 2, ..PieceTraits::default() });
+            }
+            let input = match tick % 5 {
+                0 => PillInput::shift(Orientation::Right),
+                1 => PillInput::rotate(Orientation::Left),
+                _ => PillInput::drop(),
+            };
+            events.extend(simulation.step(input, 0.05));
+        }
+
+        let replay = simulation.recording().unwrap();
+        assert!(replay.ticks.iter().any(|tick| !tick.traits.is_empty()));
+        let (replayed, replayed_events) = replay.play();
+        assert_eq!(replayed_events, events);
+        assert!(replayed.board() == simulation.board());
+        assert_eq!(replayed.outcome(), simulation.outcome());
+    }
+
+    #[test]
+    fn test_resume_carries_on_recording() {
+        let config = SimulationConfig { max_viruses: 20, ..SimulationConfig::default() };
+        let mut simulation = GameSimulation::recorded(config, 5);
+        simulation.start();
+        let input = |tick: usize| match tick % 4 {
+            0 => PillInput::shift(Orientation::Left),
+            1 => PillInput::rotate(Orientation::Right),
+            _ => PillInput::drop(),
+        };
+        for tick in 0..200 {
+            simulation.step(input(tick), 0.05);
+        }
+
+        let mut resumed = simulation.recording().unwrap().resume();
+        assert!(resumed.board() == simulation.board());
+        assert_eq!(resumed.next_pill(), simulation.next_pill());
+        for tick in 200..400 {
+            assert_eq!(resumed.step(input(tick), 0.05), simulation.step(input(tick), 0.05));
+        }
+        assert_eq!(resumed.recording().unwrap().ticks, simulation.recording().unwrap().ticks);
+    }
+}
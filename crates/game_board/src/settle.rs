@@ -0,0 +1,178 @@
+use super::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClearGroup<T: Clone + Copy + PartialEq> {
+    // Counted from 1 like `SimEvent::CellsCleared`
+    pub chain: usize,
+    pub mask: u8,
+    pub cells: Vec<(usize, usize, Cell<T>)>,
+    pub viruses: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettleStep<T: Clone + Copy + PartialEq> {
+    // One application of `Board::next`
+    Fall(Board<T>),
+    // One application of `Board::resolve` that removed at least one cell
+    Clear {
+        chain: usize,
+        mask: Vec<u8>,
+        groups: Vec<ClearGroup<T>>,
+        board: Board<T>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settlement<T: Clone + Copy + PartialEq> {
+    pub steps: Vec<SettleStep<T>>,
+    pub board: Board<T>,
+}
+
+impl<T: Clone + Copy + PartialEq> Settlement<T> {
+    pub fn chains(&self) -> usize {
+        self.steps.iter().filter(|step| matches!(step, SettleStep::Clear { .. })).count()
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = &ClearGroup<T>> {
+        self.steps.iter().flat_map(|step| match step {
+            SettleStep::Clear { groups, .. } => groups.iter(),
+            SettleStep::Fall(_) => [].iter(),
+        })
+    }
+
+    pub fn cells_removed(&self) -> usize {
+        self.groups().map(|group| group.cells.len()).sum()
+    }
+
+    pub fn viruses_removed(&self) -> usize {
+        self.groups().map(|group| group.viruses).sum()
+    }
+}
+
+impl<T: Clone + Copy + PartialEq> Board<T> {
+    pub fn settle<F>(&self, cmp: F) -> Settlement<T> where
+        F: Fn(Cell<T>, Cell<T>) -> bool {
+        self.settle_with(&MatchRules::default(), cmp)
+    }
+
+    // Alternates resolve and gravity the same way the game does after a pill locks:
+    // resolve, then apply `next` until nothing moves, then resolve again.
+    // Stacks and explosions are left to `GameSimulation`, this only sees colors
+    pub fn settle_with<F>(&self, rules: &MatchRules, cmp: F) -> Settlement<T> where
+        F: Fn(Cell<T>, Cell<T>) -> bool {
+        let mut steps = vec![];
+        let mut board = self.clone();
+        let mut chain = 1;
+        loop {
+            let (next_board, mask) = board.resolve_with(rules, &cmp);
+            if next_board == board {
+                break;
+            }
+            let groups = board.clear_groups(chain, &mask);
+            steps.push(SettleStep::Clear { chain, mask, groups, board: next_board.clone() });
+            board = next_board;
+            chain += 1;
+            loop {
+                let next_board = board.next();
+                if next_board == board {
+                    break;
+                }
+                steps.push(SettleStep::Fall(next_board.clone()));
+                board = next_board;
+            }
+        }
+        Settlement { steps, board }
+    }
+
+    fn clear_groups(&self, chain: usize, mask: &[u8]) -> Vec<ClearGroup<T>> {
+        let mut groups: Vec<ClearGroup<T>> = vec![];
+        for (index, mask_val) in mask.iter().enumerate() {
+            let cell = self.cells[index];
+            if *mask_val == 0 || cell == Cell::Empty {
+                continue;
+            }
+            let (row, col) = self.get_row_col(index);
+            let group = match groups.iter_mut().position(|group| group.mask == *mask_val) {
+                Some(position) => &mut groups[position],
+                None => {
+                    groups.push(ClearGroup { chain, mask: *mask_val, cells: vec![], viruses: 0 });
+                    groups.last_mut().unwrap()
+                },
+            };
+            if let Cell::Virus(_, _) = cell {
+                group.viruses += 1;
+            }
+            group.cells.push((row, col, cell));
+        }
+        groups.sort_by_key(|group| group.mask);
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_color(a: Cell<()>, b: Cell<()>) -> bool {
+        a.color().is_some() && a.color() == b.color()
+    }
+
+    #[test]
+    fn test_settle_stable_board_has_no_steps() {
+        let board: Board<()> = "
+            _ _ _
+            r B> Y<
+        ".parse().unwrap();
+        let settlement = board.settle(same_color);
+        assert!(settlement.steps.is_empty());
+        assert_eq!(settlement.board, board);
+    }
+
+    #[test]
+    fn test_settle_reports_single_clear() {
+        let board: Board<()> = "
+            R
+            r
+            r
+            r
+        ".parse().unwrap();
+        let settlement = board.settle(same_color);
+        assert_eq!(settlement.chains(), 1);
+        assert_eq!(settlement.cells_removed(), 4);
+        assert_eq!(settlement.viruses_removed(), 3);
+        assert_eq!(settlement.board, "_\n_\n_\n_".parse().unwrap());
+        match &settlement.steps[..] {
+            [SettleStep::Clear { chain: 1, mask, groups, .. }] => {
+                assert_eq!(mask, &vec![1, 1, 1, 1]);
+                assert_eq!(groups.len(), 1);
+                assert_eq!(groups[0].cells[0], (0, 0, Cell::Virus((), CellColor::RED)));
+            },
+            steps => panic!("unexpected steps {:?}", steps),
+        }
+    }
+
+    #[test]
+    fn test_settle_reports_chain_after_gravity() {
+        let board: Board<()> = "
+            B> Y<
+            _  R
+            b  R
+            b  R
+            b  r
+        ".parse().unwrap();
+        let settlement = board.settle(same_color);
+        assert_eq!(settlement.chains(), 2);
+        assert_eq!(settlement.viruses_removed(), 4);
+        assert_eq!(settlement.board, "
+            _ _
+            _ _
+            _ _
+            _ _
+            _ Y
+        ".parse().unwrap());
+        let falls = settlement.steps.iter().filter(|step| matches!(step, SettleStep::Fall(_))).count();
+        assert_eq!(falls, 4);
+        let chains: Vec<usize> = settlement.groups().map(|group| group.chain).collect();
+        assert_eq!(chains, vec![1, 2]);
+    }
+}
@@ -0,0 +1,971 @@
+use super::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+pub type PieceId = u32;
+
+// Most garbage a single cascade sends, one loose half per clear group
+pub const MAX_GARBAGE: usize = 4;
+
+// Most upcoming pills a board can show
+pub const MAX_PREVIEW: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AreaOfEffect {
+    Radius(u8),
+    Row,
+    Column,
+}
+
+impl Default for AreaOfEffect {
+    fn default() -> Self {
+        AreaOfEffect::Radius(0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PieceTraits {
+    // Matches the piece survives before it is cleared
+    pub stacks: usize,
+    // Stacks a match containing this pill removes from every piece in it
+    pub remove_stacks: usize,
+    pub explosive: AreaOfEffect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameOutcome {
+    Win,
+    Loss,
+}
+
+pub type VirusPolicy = fn(&mut StdRng, usize, usize) -> Option<CellColor>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    pub rows: usize,
+    pub cols: usize,
+    pub max_viruses: usize,
+    pub fall_period: f32,
+    // How long cleared pieces stay around before the board falls
+    pub resolve_period: f32,
+    pub explode_period: f32,
+    pub match_rules: MatchRules,
+    pub pill_colors: Vec<CellColor>,
+    // Seconds a pill can sit on something before it locks, zero locks it as soon as a drop fails
+    #[serde(default)]
+    pub lock_delay: f32,
+    // Moves and rotations that restart the lock delay, after that it keeps counting down
+    #[serde(default)]
+    pub lock_moves: usize,
+    // Upcoming pills kept in the queue, between 1 and `MAX_PREVIEW`
+    #[serde(default = "recorded_preview_count")]
+    pub preview_count: usize,
+    // Functions can't be saved, so a config loaded from a replay always uses the default policy
+    #[serde(skip, default = "default_virus_policy")]
+    pub virus_policy: VirusPolicy,
+}
+
+// Replays from before the queue had more than one pill in it
+fn recorded_preview_count() -> usize {
+    1
+}
+
+fn default_virus_policy() -> VirusPolicy {
+    |rng, _, _| {
+        match rng.gen_range(0..4) {
+            0 => Some(CellColor::RED),
+            1 => Some(CellColor::BLUE),
+            2 => Some(CellColor::YELLOW),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            rows: 16,
+            cols: 8,
+            max_viruses: 1,
+            fall_period: 0.2,
+            resolve_period: 0.3,
+            explode_period: 0.6,
+            match_rules: MatchRules::default(),
+            pill_colors: vec![CellColor::RED, CellColor::BLUE, CellColor::YELLOW],
+            lock_delay: 0.5,
+            lock_moves: 15,
+            preview_count: 3,
+            virus_policy: default_virus_policy(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimEvent {
+    VirusSpawned { piece: PieceId, color: CellColor, row: usize, col: usize },
+    // A pill joined the back of the queue, `position` 0 is the next one to drop
+    PillQueued { pieces: [(PieceId, CellColor); 2], position: usize },
+    // The queued pill entered the board with its first piece at (row, col) and the pivot to the right of it
+    PillAdded { pieces: [(PieceId, CellColor); 2], row: usize, col: usize },
+    // The active pill left the board for the hold slot, the pivot is the second piece
+    PillHeld { pieces: [(PieceId, CellColor); 2] },
+    PillShifted { piece: PieceId, direction: Orientation },
+    PillRotated { piece: PieceId, direction: Orientation },
+    PillLocked { piece: PieceId },
+    StacksRemoved { piece: PieceId, remaining: usize },
+    VirusRemoved { piece: PieceId, color: CellColor, row: usize, col: usize },
+    // `chain` counts the clears since the pill locked, starting at 1, and `groups` the matches in this one
+    CellsCleared { pieces: Vec<PieceId>, chain: usize, groups: usize },
+    Exploded { pieces: Vec<PieceId> },
+    // Cleared and exploded pieces are gone for good once the resolve period is over
+    ClearFinished { pieces: Vec<PieceId> },
+    // A cascade cleared two or more groups, one color per group to drop on the other boards
+    GarbageSent { colors: Vec<CellColor> },
+    // A loose pill half from another board dropped in at the top before the next pill
+    GarbageAdded { piece: PieceId, color: CellColor, row: usize, col: usize },
+    Finished(GameOutcome),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    NotStarted,
+    NeedsPill,
+    Dropping { pivot: (usize, usize) },
+    Resolving,
+    Clearing { elapsed: f32, explode: Option<f32> },
+    Falling,
+    Finished(GameOutcome),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ClearedPiece {
+    piece: PieceId,
+    row: usize,
+    col: usize,
+    exploded: bool,
+}
+
+// The rules of a single board without any rendering or input handling. Each call to
+// `step` advances the board by `dt` seconds and reports what happened as events
+#[derive(Clone, Debug)]
+pub struct GameSimulation {
+    config: SimulationConfig,
+    board: Board<PieceId>,
+    pieces: HashMap<PieceId, PieceTraits>,
+    queue: VecDeque<[(PieceId, CellColor); 2]>,
+    held: Option<[(PieceId, CellColor); 2]>,
+    // Cleared when a pill is held and set again once a pill locks
+    can_hold: bool,
+    // How long the active pill has been resting on something, and how many times that was restarted
+    lock_elapsed: Option<f32>,
+    lock_resets: usize,
+    cleared: Vec<ClearedPiece>,
+    phase: Phase,
+    fall_elapsed: f32,
+    next_id: PieceId,
+    rng: StdRng,
+    // Colors of the groups cleared since the last pill locked
+    cascade: Vec<CellColor>,
+    chain: usize,
+    garbage: Vec<CellColor>,
+    recording: Option<Replay>,
+    // Trait changes and garbage since the last step, recorded with the next tick
+    pending_traits: Vec<(PieceId, PieceTraits)>,
+    pending_garbage: Vec<CellColor>,
+}
+
+impl GameSimulation {
+    pub fn new(config: SimulationConfig, seed: u64) -> Self {
+        let board = Board::new(config.rows, config.cols);
+        Self::with_board(config, board, seed)
+    }
+
+    // Same as `new`, but keeps every tick so the game can be saved and played back with `Replay::play`
+    pub fn recorded(config: SimulationConfig, seed: u64) -> Self {
+        let recording = Replay::new(config.clone(), seed);
+        Self { recording: Some(recording), ..Self::new(config, seed) }
+    }
+
+    // Starts from an existing board, the config's size is ignored
+    pub fn with_board(mut config: SimulationConfig, board: Board<PieceId>, seed: u64) -> Self {
+        config.rows = board.rows;
+        config.cols = board.cols;
+        let pieces: HashMap<PieceId, PieceTraits> = board.cells.iter()
+            .filter_map(|cell| cell.get())
+            .map(|piece| (piece, PieceTraits::default()))
+            .collect();
+        let next_id = pieces.keys().max().map_or(0, |id| id + 1);
+        Self {
+            config,
+            board,
+            pieces,
+            queue: VecDeque::new(),
+            held: None,
+            can_hold: true,
+            lock_elapsed: None,
+            lock_resets: 0,
+            cleared: vec![],
+            phase: Phase::NotStarted,
+            fall_elapsed: 0.0,
+            next_id,
+            rng: StdRng::seed_from_u64(seed),
+            cascade: vec![],
+            chain: 0,
+            garbage: vec![],
+            recording: None,
+            pending_traits: vec![],
+            pending_garbage: vec![],
+        }
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    pub fn board(&self) -> &Board<PieceId> {
+        &self.board
+    }
+
+    pub fn traits(&self, piece: PieceId) -> Option<&PieceTraits> {
+        self.pieces.get(&piece)
+    }
+
+    pub fn set_traits(&mut self, piece: PieceId, traits: PieceTraits) {
+        let Some(current) = self.pieces.get_mut(&piece) else { return };
+        if *current == traits {
+            return;
+        }
+        *current = traits;
+        if self.recording.is_some() {
+            self.pending_traits.push((piece, traits));
+        }
+    }
+
+    // Queues garbage sent by another board, it drops in before the next pill
+    pub fn receive_garbage(&mut self, colors: &[CellColor]) {
+        self.garbage.extend_from_slice(colors);
+        if self.recording.is_some() {
+            self.pending_garbage.extend_from_slice(colors);
+        }
+    }
+
+    pub fn garbage(&self) -> &[CellColor] {
+        &self.garbage
+    }
+
+    pub fn recording(&self) -> Option<&Replay> {
+        self.recording.as_ref()
+    }
+
+    pub fn next_pill(&self) -> Option<[(PieceId, CellColor); 2]> {
+        self.queue.front().copied()
+    }
+
+    pub fn held_pill(&self) -> Option<[(PieceId, CellColor); 2]> {
+        self.held
+    }
+
+    pub fn can_hold(&self) -> bool {
+        self.can_hold
+    }
+
+    // The upcoming pills, next first
+    pub fn queue(&self) -> impl Iterator<Item = &[(PieceId, CellColor); 2]> {
+        self.queue.iter()
+    }
+
+    // Position of the half the player controls while a pill is dropping
+    pub fn pivot(&self) -> Option<(usize, usize)> {
+        match self.phase {
+            Phase::Dropping { pivot } => Some(pivot),
+            _ => None,
+        }
+    }
+
+    pub fn pivot_piece(&self) -> Option<PieceId> {
+        self.pivot().and_then(|(row, col)| self.board.get(row, col).get())
+    }
+
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        match self.phase {
+            Phase::Finished(outcome) => Some(outcome),
+            _ => None,
+        }
+    }
+
+    fn new_piece(&mut self) -> PieceId {
+        let piece = self.next_id;
+        self.next_id += 1;
+        self.pieces.insert(piece, PieceTraits::default());
+        piece
+    }
+
+    // Spawns the viruses and fills the pill queue
+    pub fn start(&mut self) -> Vec<SimEvent> {
+        let mut events = vec![];
+        if self.phase != Phase::NotStarted {
+            return events;
+        }
+        let mut viruses_remaining = self.config.max_viruses;
+        for row in 0..self.board.rows - 1 {
+            for col in 0..self.board.cols {
+                if viruses_remaining < 1 {
+                    break;
+                }
+                if let Some(color) = (self.config.virus_policy)(&mut self.rng, row, col) {
+                    viruses_remaining -= 1;
+                    let piece = self.new_piece();
+                    self.board.set(row, col, Cell::Virus(piece, color));
+                    events.push(SimEvent::VirusSpawned { piece, color, row, col });
+                }
+            }
+        }
+        while self.queue.len() < self.config.preview_count.clamp(1, MAX_PREVIEW) {
+            self.queue_pill(&mut events);
+        }
+        self.phase = Phase::NeedsPill;
+        events
+    }
+
+    pub fn step(&mut self, input: PillInput, dt: f32) -> Vec<SimEvent> {
+        let mut events = vec![];
+        if let (Some(recording), false) = (&mut self.recording, matches!(self.phase, Phase::Finished(_))) {
+            let traits = std::mem::take(&mut self.pending_traits);
+            let garbage = std::mem::take(&mut self.pending_garbage);
+            recording.ticks.push(ReplayTick { dt, input, traits, garbage });
+        }
+        match self.phase {
+            Phase::NotStarted | Phase::Finished(_) => return events,
+            Phase::NeedsPill => self.add_pill(&mut events),
+            Phase::Dropping { pivot } => self.move_pill(pivot, input, dt, &mut events),
+            Phase::Resolving => self.resolve(&mut events),
+            Phase::Clearing { elapsed, explode } => self.clear(elapsed, explode, dt, &mut events),
+            Phase::Falling => self.fall(dt),
+        }
+        if self.outcome().is_none() && self.board.virus_count() < 1 {
+            self.finish(GameOutcome::Win, &mut events);
+        }
+        events
+    }
+
+    fn finish(&mut self, outcome: GameOutcome, events: &mut Vec<SimEvent>) {
+        self.phase = Phase::Finished(outcome);
+        events.push(SimEvent::Finished(outcome));
+    }
+
+    fn queue_pill(&mut self, events: &mut Vec<SimEvent>) {
+        let colors = &self.config.pill_colors;
+        let first = colors[self.rng.gen_range(0..colors.len())];
+        let second = colors[self.rng.gen_range(0..colors.len())];
+        let pieces = [(self.new_piece(), first), (self.new_piece(), second)];
+        self.queue.push_back(pieces);
+        events.push(SimEvent::PillQueued { pieces, position: self.queue.len() - 1 });
+    }
+
+    fn add_pill(&mut self, events: &mut Vec<SimEvent>) {
+        // With the top row full the garbage keeps waiting and the pill tops out
+        if !self.garbage.is_empty() && self.add_garbage(events) {
+            return;
+        }
+        self.enter_next_pill(events);
+    }
+
+    fn enter_next_pill(&mut self, events: &mut Vec<SimEvent>) {
+        let Some(pieces) = self.next_pill() else { return };
+        if self.enter_pill(pieces, events) {
+            self.queue.pop_front();
+            self.queue_pill(events);
+        }
+    }
+
+    // Puts a pill at the top of the board, topping out if there is no room for it
+    fn enter_pill(&mut self, pieces: [(PieceId, CellColor); 2], events: &mut Vec<SimEvent>) -> bool {
+        let [(first, first_color), (second, second_color)] = pieces;
+        let (row, col) = (self.board.rows - 1, self.board.cols / 2 - 1);
+        if self.board.get(row, col) != Cell::Empty || self.board.get(row, col + 1) != Cell::Empty {
+            self.finish(GameOutcome::Loss, events);
+            return false;
+        }
+        self.board.set(row, col, Cell::Pill(first, first_color, Some(Orientation::Right)));
+        self.board.set(row, col + 1, Cell::Pill(second, second_color, Some(Orientation::Left)));
+        events.push(SimEvent::PillAdded { pieces, row, col });
+        self.phase = Phase::Dropping { pivot: (row, col + 1) };
+        self.lock_elapsed = None;
+        self.lock_resets = 0;
+        true
+    }
+
+    // Takes the active pill off the board and brings in the held one, or the next one if nothing is held
+    fn hold_pill(&mut self, pivot: (usize, usize), events: &mut Vec<SimEvent>) {
+        if !self.can_hold {
+            return;
+        }
+        let (Cell::Pill(piece, color, _), Some((Cell::Pill(other, other_color, _), other_row, other_col))) =
+            self.board.get_paired(pivot.0, pivot.1) else { return };
+        self.board.set(pivot.0, pivot.1, Cell::Empty);
+        self.board.set(other_row, other_col, Cell::Empty);
+        let pieces = [(other, other_color), (piece, color)];
+        events.push(SimEvent::PillHeld { pieces });
+        self.can_hold = false;
+        match self.held.replace(pieces) {
+            Some(held) => { self.enter_pill(held, events); },
+            None => self.enter_next_pill(events),
+        }
+    }
+
+    // Drops the queued garbage into random free columns of the top row, anything without room waits for the next pill.
+    // False when there was no room for any of it
+    fn add_garbage(&mut self, events: &mut Vec<SimEvent>) -> bool {
+        let row = self.board.rows - 1;
+        let mut cols: Vec<usize> = (0..self.board.cols)
+            .filter(|col| self.board.get(row, *col) == Cell::Empty)
+            .collect();
+        if cols.is_empty() {
+            return false;
+        }
+        cols.shuffle(&mut self.rng);
+        let count = std::cmp::min(cols.len(), self.garbage.len());
+        let colors: Vec<CellColor> = self.garbage.drain(..count).collect();
+        for (col, color) in cols.into_iter().zip(colors) {
+            let piece = self.new_piece();
+            self.board.set(row, col, Cell::Pill(piece, color, None));
+            events.push(SimEvent::GarbageAdded { piece, color, row, col });
+        }
+        self.phase = Phase::Falling;
+        true
+    }
+
+    fn move_pill(&mut self, pivot: (usize, usize), input: PillInput, dt: f32, events: &mut Vec<SimEvent>) {
+        if input.hold {
+            self.hold_pill(pivot, events);
+            return;
+        }
+        let Some(piece) = self.board.get(pivot.0, pivot.1).get() else { return };
+        if input.hard_drop {
+            let mut pivot = pivot;
+            while let PillInputResult { locked: false, pivot: next, .. } = self.board.apply_pill_input(pivot, PillInput::drop()) {
+                pivot = next;
+            }
+            self.lock_pill(piece, events);
+            return;
+        }
+        let mut pivot = pivot;
+        if input != PillInput::default() {
+            let result = self.board.apply_pill_input(pivot, input);
+            if let (true, Some(direction)) = (result.rotated, input.rotate) {
+                events.push(SimEvent::PillRotated { piece, direction });
+            }
+            if result.moved {
+                if let (true, Some(direction)) = (result.pivot.1 != pivot.1, input.shift) {
+                    events.push(SimEvent::PillShifted { piece, direction });
+                }
+                pivot = result.pivot;
+                self.phase = Phase::Dropping { pivot };
+            }
+            if result.locked && self.lock_elapsed.is_none() {
+                if self.config.lock_delay <= 0.0 {
+                    self.lock_pill(piece, events);
+                    return;
+                }
+                self.lock_elapsed = Some(0.0);
+            } else if (result.moved || result.rotated) && self.lock_elapsed.is_some() && self.lock_resets < self.config.lock_moves {
+                self.lock_elapsed = Some(0.0);
+                self.lock_resets += 1;
+            }
+        }
+
+        let Some(elapsed) = self.lock_elapsed else { return };
+        // Sliding off a ledge lets the pill fall again until it lands on something else
+        let mut board = self.board.clone();
+        if !board.apply_pill_input(pivot, PillInput::drop()).locked {
+            self.lock_elapsed = None;
+            return;
+        }
+        let elapsed = elapsed + dt;
+        if elapsed >= self.config.lock_delay {
+            self.lock_pill(piece, events);
+        } else {
+            self.lock_elapsed = Some(elapsed);
+        }
+    }
+
+    fn lock_pill(&mut self, piece: PieceId, events: &mut Vec<SimEvent>) {
+        events.push(SimEvent::PillLocked { piece });
+        self.can_hold = true;
+        self.lock_elapsed = None;
+        self.phase = Phase::Resolving;
+    }
+
+    // One step of the cascade `Board::settle_with` runs all at once. It is kept separate because
+    // pieces here can survive a match through stacks or explode, and each clear and fall is timed
+    fn resolve(&mut self, events: &mut Vec<SimEvent>) {
+        let (mut next_board, mask) = self.board.resolve_with(&self.config.match_rules, |l, r| l.color() == r.color());
+        if next_board == self.board {
+            if self.cascade.len() >= 2 {
+                let colors = self.cascade.drain(..).take(MAX_GARBAGE).collect();
+                events.push(SimEvent::GarbageSent { colors });
+            }
+            self.cascade.clear();
+            self.chain = 0;
+            self.phase = Phase::NeedsPill;
+            return;
+        }
+
+        let mut groups: Vec<u8> = mask.iter().copied().filter(|group| *group > 0).collect();
+        groups.sort_unstable();
+        groups.dedup();
+        let group_count = groups.len();
+        self.chain += 1;
+        for group in groups {
+            let index = mask.iter().position(|value| *value == group);
+            if let Some(color) = index.and_then(|index| self.board.cells[index].color()) {
+                self.cascade.push(color);
+            }
+        }
+
+        // The most stacks any pill in each match group removes, indexed by mask value
+        let mut mask_lookup: Vec<Option<usize>> = vec![None; mask.len()];
+        for (index, cell) in self.board.cells.iter().enumerate() {
+            let mask_index = mask[index] as usize;
+            if let (true, Cell::Pill(piece, _, _)) = (mask_index > 0, cell) {
+                let remove_stacks = self.pieces.get(piece).map_or(0, |traits| traits.remove_stacks);
+                if remove_stacks > mask_lookup[mask_index].unwrap_or(0) {
+                    mask_lookup[mask_index] = Some(remove_stacks);
+                }
+            }
+        }
+
+        let mut cleared = vec![];
+        let mut explode = None;
+        for row in 0..self.board.rows {
+            for col in 0..self.board.cols {
+                if next_board.get(row, col) != Cell::Empty {
+                    continue;
+                }
+                let cell = self.board.get(row, col);
+                let Some(piece) = cell.get() else { continue };
+                let traits = self.pieces.entry(piece).or_default();
+                let stacks_removed = mask_lookup[mask[self.board.get_index(row, col)] as usize].unwrap_or(1);
+                let should_clear = stacks_removed > traits.stacks;
+                if traits.stacks > 0 {
+                    traits.stacks -= std::cmp::min(stacks_removed, traits.stacks);
+                    events.push(SimEvent::StacksRemoved { piece, remaining: traits.stacks });
+                }
+                if should_clear {
+                    if let AreaOfEffect::Radius(1..) = traits.explosive {
+                        explode = Some(0.0);
+                    }
+                    if let Cell::Virus(_, color) = cell {
+                        events.push(SimEvent::VirusRemoved { piece, color, row, col });
+                    }
+                    self.cleared.push(ClearedPiece { piece, row, col, exploded: false });
+                    cleared.push(piece);
+                } else {
+                    next_board.set(row, col, cell);
+                }
+            }
+        }
+        events.push(SimEvent::CellsCleared { pieces: cleared, chain: self.chain, groups: group_count });
+        self.board = next_board;
+        self.phase = Phase::Clearing { elapsed: 0.0, explode };
+    }
+
+    fn clear(&mut self, elapsed: f32, explode: Option<f32>, dt: f32, events: &mut Vec<SimEvent>) {
+        // Explosions hold up the rest of the resolve until they are done
+        if let Some(explode) = explode {
+            let explode = explode + dt;
+            self.phase = if explode >= self.config.explode_period {
+                Phase::Clearing { elapsed, explode: self.explode(events) }
+            } else {
+                Phase::Clearing { elapsed, explode: Some(explode) }
+            };
+            return;
+        }
+        let elapsed = elapsed + dt;
+        if elapsed < self.config.resolve_period {
+            self.phase = Phase::Clearing { elapsed, explode: None };
+            return;
+        }
+        let pieces: Vec<PieceId> = self.cleared.drain(..).map(|cleared| cleared.piece).collect();
+        for piece in pieces.iter() {
+            self.pieces.remove(piece);
+        }
+        events.push(SimEvent::ClearFinished { pieces });
+        self.phase = Phase::Falling;
+    }
+
+    // Clears the area around every cleared explosive piece, returning a new explode timer
+    // when that clears more explosives
+    fn explode(&mut self, events: &mut Vec<SimEvent>) -> Option<f32> {
+        let mut exploded = vec![];
+        let mut explode = None;
+        for index in 0..self.cleared.len() {
+            let ClearedPiece { piece, row, col, exploded: done } = self.cleared[index];
+            self.cleared[index].exploded = true;
+            let area = self.pieces.get(&piece).map_or(AreaOfEffect::default(), |traits| traits.explosive);
+            if done {
+                continue;
+            }
+            for (row, col) in self.area(row, col, area) {
+                if let Some(hit) = self.board.get(row, col).get() {
+                    if let Some(AreaOfEffect::Radius(1..)) = self.pieces.get(&hit).map(|traits| traits.explosive) {
+                        explode = Some(0.0);
+                    }
+                    self.cleared.push(ClearedPiece { piece: hit, row, col, exploded: false });
+                    exploded.push(hit);
+                }
+                self.board.set(row, col, Cell::Empty);
+            }
+        }
+        events.push(SimEvent::Exploded { pieces: exploded });
+        explode
+    }
+
+    fn area(&self, row: usize, col: usize, area: AreaOfEffect) -> Vec<(usize, usize)> {
+        let (rows, cols) = (self.board.rows as isize, self.board.cols as isize);
+        let (row, col) = (row as isize, col as isize);
+        let positions: Vec<(isize, isize)> = match area {
+            AreaOfEffect::Radius(radius) => (1..=radius as isize)
+                .flat_map(|r| [(-r, 0), (r, 0), (0, -r), (0, r), (r, -r), (-r, -r), (r, r), (-r, r)])
+                .map(|(dr, dc)| (row + dr, col + dc))
+                .collect(),
+            AreaOfEffect::Row => (0..cols).map(|col| (row, col)).collect(),
+            AreaOfEffect::Column => (0..rows).map(|row| (row, col)).collect(),
+        };
+        positions.into_iter()
+            .filter(|(row, col)| (0..rows).contains(row) && (0..cols).contains(col))
+            .map(|(row, col)| (row as usize, col as usize))
+            .collect()
+    }
+
+    fn fall(&mut self, dt: f32) {
+        self.fall_elapsed += dt;
+        if self.fall_elapsed < self.config.fall_period {
+            return;
+        }
+        self.fall_elapsed -= self.config.fall_period;
+        let next_board = self.board.next();
+        if next_board != self.board {
+            self.board = next_board;
+        } else {
+            self.phase = Phase::Resolving;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SimulationConfig {
+        SimulationConfig {
+            max_viruses: 0,
+            pill_colors: vec![CellColor::RED],
+            // Pills lock as soon as they land unless a test asks for a delay
+            lock_delay: 0.0,
+            lock_moves: 0,
+            ..SimulationConfig::default()
+        }
+    }
+
+    fn simulation(board: &str) -> GameSimulation {
+        let mut id = 0;
+        let board = Board::parse_with(board, |_, _| { id += 1; id }).unwrap();
+        GameSimulation::with_board(config(), board, 0)
+    }
+
+    // Steps with no input until the board wants another pill
+    fn run_until_settled(simulation: &mut GameSimulation) -> Vec<SimEvent> {
+        let mut events = vec![];
+        for _ in 0..1000 {
+            events.extend(simulation.step(PillInput::default(), 0.1));
+            if simulation.pivot().is_some() || simulation.outcome().is_some() {
+                break;
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_start_spawns_viruses_and_queues_a_pill() {
+        let mut simulation = GameSimulation::new(SimulationConfig { max_viruses: 10, ..SimulationConfig::default() }, 7);
+        let events = simulation.start();
+        let spawned = events.iter().filter(|event| matches!(event, SimEvent::VirusSpawned { .. })).count();
+        assert_eq!(spawned, 10);
+        assert_eq!(simulation.board().virus_count(), 10);
+        assert!(matches!(events.last(), Some(SimEvent::PillQueued { .. })));
+        assert!(simulation.next_pill().is_some());
+    }
+
+    #[test]
+    fn test_queue_keeps_preview_count_pills_in_order() {
+        let mut simulation = GameSimulation::new(SimulationConfig { preview_count: 3, ..SimulationConfig::default() }, 11);
+        let events = simulation.start();
+        let positions: Vec<usize> = events.iter()
+            .filter_map(|event| if let SimEvent::PillQueued { position, .. } = event { Some(*position) } else { None })
+            .collect();
+        assert_eq!(positions, vec![0, 1, 2]);
+        let queued: Vec<_> = simulation.queue().copied().collect();
+        let events = simulation.step(PillInput::default(), 0.1);
+        assert!(events.contains(&SimEvent::PillAdded { pieces: queued[0], row: 15, col: 3 }));
+        assert!(events.iter().any(|event| matches!(event, SimEvent::PillQueued { position: 2, .. })));
+        assert_eq!(simulation.queue().copied().collect::<Vec<_>>()[..2], queued[1..]);
+    }
+
+    #[test]
+    fn test_same_seed_plays_the_same_game() {
+        let play = |seed| {
+            let mut simulation = GameSimulation::new(SimulationConfig { max_viruses: 20, ..SimulationConfig::default() }, seed);
+            let mut events = simulation.start();
+            for tick in 0..400 {
+                let input = if tick % 3 == 0 { PillInput::drop() } else { PillInput::shift(Orientation::Left) };
+                events.extend(simulation.step(input, 0.05));
+            }
+            events
+        };
+        assert_eq!(play(3), play(3));
+        assert_ne!(play(3), play(4));
+    }
+
+    #[test]
+    fn test_drop_locks_pill_and_clears_match() {
+        let mut simulation = simulation("
+            _ _ _ _
+            _ _ _ _
+            _ _ _ r
+            _ _ _ r
+            _ _ _ r
+        ");
+        simulation.start();
+        let events = simulation.step(PillInput::default(), 0.0);
+        assert!(matches!(events[0], SimEvent::PillAdded { row: 4, col: 1, .. }));
+        assert_eq!(simulation.pivot(), Some((4, 2)));
+
+        // Stand the pill up over the viruses and drop it on them
+        simulation.step(PillInput::drop(), 0.0);
+        simulation.step(PillInput::rotate(Orientation::Right), 0.0);
+        simulation.step(PillInput::shift(Orientation::Right), 0.0);
+        assert_eq!(simulation.pivot().map(|(_, col)| col), Some(3));
+        let mut events = vec![];
+        while simulation.pivot().is_some() {
+            events = simulation.step(PillInput::drop(), 0.0);
+        }
+        assert!(matches!(events[..], [SimEvent::PillLocked { .. }]));
+
+        let events = run_until_settled(&mut simulation);
+        let removed = events.iter().filter(|event| matches!(event, SimEvent::VirusRemoved { .. })).count();
+        assert_eq!(removed, 3);
+        assert!(events.contains(&SimEvent::Finished(GameOutcome::Win)));
+        assert_eq!(simulation.outcome(), Some(GameOutcome::Win));
+    }
+
+    #[test]
+    fn test_blocked_spawn_loses() {
+        let mut simulation = simulation("
+            _ B> R< _
+            _ _  _  y
+        ");
+        simulation.start();
+        let events = simulation.step(PillInput::default(), 0.0);
+        assert_eq!(events, vec![SimEvent::Finished(GameOutcome::Loss)]);
+    }
+
+    #[test]
+    fn test_stacked_virus_survives_a_match() {
+        let mut simulation = simulation("
+            _ _ _ _
+            _ _ _ R
+            _ _ _ R
+            _ _ _ R
+            y _ _ r
+        ");
+        let virus = simulation.board().get(0, 3).get().unwrap();
+        simulation.set_traits(virus, PieceTraits { stacks: 1, ..PieceTraits::default() });
+        simulation.phase = Phase::Resolving;
+        let events = run_until_settled(&mut simulation);
+        assert!(events.contains(&SimEvent::StacksRemoved { piece: virus, remaining: 0 }));
+        assert!(!events.iter().any(|event| matches!(event, SimEvent::VirusRemoved { .. })));
+        assert_eq!(simulation.board().get(0, 3), Cell::Virus(virus, CellColor::RED));
+        assert_eq!(simulation.board().virus_count(), 2);
+    }
+
+    #[test]
+    fn test_cascade_of_two_groups_sends_garbage() {
+        let mut simulation = simulation("
+            _ _ _ _
+            R _ _ B
+            R _ _ B
+            R _ _ B
+            r y y b
+        ");
+        simulation.phase = Phase::Resolving;
+        let events = run_until_settled(&mut simulation);
+        let sent: Vec<&SimEvent> = events.iter().filter(|event| matches!(event, SimEvent::GarbageSent { .. })).collect();
+        assert!(matches!(sent[..], [SimEvent::GarbageSent { colors }] if colors.len() == 2
+            && colors.contains(&CellColor::RED)
+            && colors.contains(&CellColor::BLUE)));
+    }
+
+    #[test]
+    fn test_chain_counts_clears_in_a_cascade() {
+        let mut simulation = simulation("
+            _ _ _ _
+            Y _ _ _
+            R _ _ _
+            R _ _ _
+            R _ _ _
+            r y y y
+        ");
+        simulation.phase = Phase::Resolving;
+        let events = run_until_settled(&mut simulation);
+        let clears: Vec<(usize, usize)> = events.iter().filter_map(|event| match event {
+            SimEvent::CellsCleared { chain, groups, .. } => Some((*chain, *groups)),
+            _ => None,
+        }).collect();
+        assert_eq!(clears, vec![(1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn test_single_group_sends_no_garbage() {
+        let mut simulation = simulation("
+            _ _ _ _
+            R _ _ _
+            R _ _ _
+            R _ _ _
+            r y y b
+        ");
+        simulation.phase = Phase::Resolving;
+        let events = run_until_settled(&mut simulation);
+        assert!(!events.iter().any(|event| matches!(event, SimEvent::GarbageSent { .. })));
+    }
+
+    #[test]
+    fn test_received_garbage_drops_before_next_pill() {
+        let mut simulation = simulation("
+            _ _ _ _
+            _ _ _ _
+            _ _ _ _
+            y _ _ _
+        ");
+        simulation.start();
+        simulation.receive_garbage(&[CellColor::RED, CellColor::BLUE]);
+        let events = simulation.step(PillInput::default(), 0.0);
+        let added: Vec<(usize, CellColor)> = events.iter().filter_map(|event| match event {
+            SimEvent::GarbageAdded { row, color, .. } => Some((*row, *color)),
+            _ => None,
+        }).collect();
+        assert_eq!(added.len(), 2);
+        assert!(added.iter().all(|(row, _)| *row == 3));
+        assert!(simulation.garbage().is_empty());
+        assert_eq!(simulation.pivot(), None);
+
+        run_until_settled(&mut simulation);
+        assert!(simulation.pivot().is_some());
+        let loose = simulation.board().cells.iter()
+            .filter(|cell| matches!(cell, Cell::Pill(_, _, None)))
+            .count();
+        assert_eq!(loose, 2);
+    }
+
+    #[test]
+    fn test_garbage_with_a_full_top_row_tops_out() {
+        let mut simulation = simulation("
+            y y y y
+            _ _ _ _
+            _ _ _ _
+            _ _ _ _
+        ");
+        simulation.start();
+        simulation.receive_garbage(&[CellColor::RED]);
+        let events = run_until_settled(&mut simulation);
+        assert!(!events.iter().any(|event| matches!(event, SimEvent::GarbageAdded { .. })));
+        assert!(events.contains(&SimEvent::Finished(GameOutcome::Loss)));
+        assert_eq!(simulation.outcome(), Some(GameOutcome::Loss));
+    }
+
+    fn locked(events: &[SimEvent]) -> bool {
+        events.iter().any(|event| matches!(event, SimEvent::PillLocked { .. }))
+    }
+
+    #[test]
+    fn test_lock_delay_restarts_on_moves_up_to_the_limit() {
+        let board = Board::parse_with("
+            _ _ _ _
+            _ _ _ _
+            _ _ _ _
+            _ _ _ r
+        ", |_, _| 1).unwrap();
+        let config = SimulationConfig { lock_delay: 0.5, lock_moves: 1, ..config() };
+        let mut simulation = GameSimulation::with_board(config, board, 0);
+        simulation.start();
+        simulation.step(PillInput::default(), 0.0);
+        for _ in 0..4 {
+            assert!(!locked(&simulation.step(PillInput::drop(), 0.0)));
+        }
+        assert!(!locked(&simulation.step(PillInput::default(), 0.3)));
+        // The first move restarts the delay, the second one doesn't
+        assert!(!locked(&simulation.step(PillInput::shift(Orientation::Left), 0.0)));
+        assert!(!locked(&simulation.step(PillInput::default(), 0.3)));
+        assert!(!locked(&simulation.step(PillInput::shift(Orientation::Right), 0.0)));
+        assert!(locked(&simulation.step(PillInput::default(), 0.3)));
+    }
+
+    #[test]
+    fn test_hard_drop_locks_without_delay() {
+        let board = Board::parse_with("
+            _ _ _ _
+            _ _ _ _
+            _ _ _ _
+            _ _ _ r
+        ", |_, _| 1).unwrap();
+        let mut simulation = GameSimulation::with_board(SimulationConfig { lock_delay: 0.5, ..config() }, board, 0);
+        simulation.start();
+        simulation.step(PillInput::default(), 0.0);
+        assert!(locked(&simulation.step(PillInput::hard_drop(), 0.0)));
+        assert!(matches!(simulation.board().get(0, 1), Cell::Pill(..)) && matches!(simulation.board().get(0, 2), Cell::Pill(..)));
+    }
+
+    #[test]
+    fn test_hold_swaps_once_per_lock() {
+        let mut simulation = GameSimulation::new(SimulationConfig { preview_count: 2, ..SimulationConfig::default() }, 3);
+        simulation.start();
+        let queued: Vec<_> = simulation.queue().copied().collect();
+        simulation.step(PillInput::default(), 0.1);
+        let events = simulation.step(PillInput::hold(), 0.1);
+        assert!(events.contains(&SimEvent::PillHeld { pieces: queued[0] }));
+        assert!(events.contains(&SimEvent::PillAdded { pieces: queued[1], row: 15, col: 3 }));
+        assert_eq!(simulation.held_pill(), Some(queued[0]));
+
+        // Holding again before the pill locks does nothing
+        assert!(simulation.step(PillInput::hold(), 0.1).is_empty());
+        assert_eq!(simulation.pivot_piece(), Some(queued[1][1].0));
+
+        while !simulation.can_hold() {
+            simulation.step(PillInput::drop(), 0.1);
+        }
+        run_until_settled(&mut simulation);
+        let events = simulation.step(PillInput::hold(), 0.1);
+        assert!(events.contains(&SimEvent::PillAdded { pieces: queued[0], row: 15, col: 3 }));
+        assert_eq!(simulation.pivot_piece(), Some(queued[0][1].0));
+    }
+
+    #[test]
+    fn test_explosive_clears_around_it() {
+        let mut simulation = simulation("
+            _ _ _
+            _ R _
+            _ R _
+            _ R _
+            y r y
+        ");
+        let virus = simulation.board().get(0, 1).get().unwrap();
+        simulation.set_traits(virus, PieceTraits { explosive: AreaOfEffect::Radius(1), ..PieceTraits::default() });
+        simulation.phase = Phase::Resolving;
+        let events = run_until_settled(&mut simulation);
+        let exploded: Vec<&SimEvent> = events.iter().filter(|event| matches!(event, SimEvent::Exploded { .. })).collect();
+        assert!(matches!(exploded[..], [SimEvent::Exploded { pieces }] if pieces.len() == 2));
+        assert_eq!(simulation.outcome(), Some(GameOutcome::Win));
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use std::collections::{BTreeMap, BTreeSet};
+use super::*;
+
+// How far the stick has to be pushed to count as the d-pad
+const STICK_THRESHOLD: f32 = 0.5;
+
+// The actions a player is holding this frame from their keys and gamepad together
+#[derive(Clone, Debug, Default)]
+pub struct ActionState {
+    pressed: BTreeSet<InputAction>,
+    just_pressed: BTreeSet<InputAction>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+}
+
+// Action state for every player, indexed by player number
+#[derive(Default, Resource)]
+pub struct PlayerActions(Vec<ActionState>);
+
+impl PlayerActions {
+    pub fn player(&self, number: usize) -> Option<&ActionState> {
+        self.0.get(number)
+    }
+}
+
+// Which player each connected gamepad plays for
+#[derive(Default, Resource)]
+pub struct GamepadAssignments(BTreeMap<usize, Gamepad>);
+
+impl GamepadAssignments {
+    pub fn gamepad(&self, player: usize) -> Option<Gamepad> {
+        self.0.get(&player).copied()
+    }
+
+    // A gamepad only plays for one player, so it is taken away from anyone else using it
+    pub fn assign(&mut self, player: usize, gamepad: Option<Gamepad>) {
+        self.0.retain(|_, assigned| Some(*assigned) != gamepad);
+        match gamepad {
+            Some(gamepad) => { self.0.insert(player, gamepad); },
+            None => { self.0.remove(&player); },
+        }
+    }
+}
+
+// New gamepads go to the first player without one
+pub(crate) fn assign_gamepads(
+    mut events: EventReader<GamepadConnectionEvent>,
+    mut assignments: ResMut<GamepadAssignments>,
+    input_map: Res<InputMap>,
+) {
+    for event in events.read() {
+        match event.connection {
+            GamepadConnection::Connected(_) => {
+                if assignments.0.values().any(|gamepad| *gamepad == event.gamepad) {
+                    continue;
+                }
+                if let Some(player) = (0..input_map.players.len()).find(|player| assignments.gamepad(*player).is_none()) {
+                    info!("Assigning gamepad {:?} to player {}", event.gamepad, player + 1);
+                    assignments.assign(player, Some(event.gamepad));
+                }
+            },
+            GamepadConnection::Disconnected => {
+                assignments.0.retain(|_, gamepad| *gamepad != event.gamepad);
+            },
+        }
+    }
+}
+
+fn stick_pressed(axes: &Axis<GamepadAxis>, gamepad: Gamepad, action: InputAction) -> bool {
+    let axis = |axis_type| axes.get(GamepadAxis::new(gamepad, axis_type)).unwrap_or(0.0);
+    match action {
+        InputAction::MoveLeft => axis(GamepadAxisType::LeftStickX) < -STICK_THRESHOLD,
+        InputAction::MoveRight => axis(GamepadAxisType::LeftStickX) > STICK_THRESHOLD,
+        InputAction::SoftDrop => axis(GamepadAxisType::LeftStickY) < -STICK_THRESHOLD,
+        _ => false,
+    }
+}
+
+pub(crate) fn update_player_actions(
+    mut actions: ResMut<PlayerActions>,
+    input_map: Res<InputMap>,
+    assignments: Res<GamepadAssignments>,
+    keyboard: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+) {
+    actions.0.resize_with(input_map.players.len(), ActionState::default);
+    for (player, keys) in input_map.players.iter().enumerate() {
+        let gamepad = assignments.gamepad(player);
+        let pressed: BTreeSet<InputAction> = InputAction::ALL.into_iter()
+            .filter(|action| {
+                keys.key(*action).is_some_and(|key| keyboard.pressed(key))
+                    || gamepad.is_some_and(|gamepad| {
+                        input_map.gamepad.button(*action)
+                            .is_some_and(|button| buttons.pressed(GamepadButton::new(gamepad, button)))
+                            || stick_pressed(&axes, gamepad, *action)
+                    })
+            })
+            .collect();
+        let state = &mut actions.0[player];
+        state.just_pressed = pressed.difference(&state.pressed).copied().collect();
+        state.pressed = pressed;
+    }
+}
@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use pills_core::*;
+
+pub const INPUT_MAP_FILE: &str = "input.ron";
+
+pub fn input_map_path() -> PathBuf {
+    data_path(INPUT_MAP_FILE)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Hold,
+    Pause,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 8] = [
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::SoftDrop,
+        InputAction::HardDrop,
+        InputAction::RotateClockwise,
+        InputAction::RotateCounterClockwise,
+        InputAction::Hold,
+        InputAction::Pause,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputAction::MoveLeft => "Move Left",
+            InputAction::MoveRight => "Move Right",
+            InputAction::SoftDrop => "Soft Drop",
+            InputAction::HardDrop => "Hard Drop",
+            InputAction::RotateClockwise => "Rotate CW",
+            InputAction::RotateCounterClockwise => "Rotate CCW",
+            InputAction::Hold => "Hold",
+            InputAction::Pause => "Pause",
+        }
+    }
+}
+
+// The key bound to each action for one player
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings(pub BTreeMap<InputAction, KeyCode>);
+
+impl KeyBindings {
+    pub fn arrows() -> Self {
+        Self(BTreeMap::from([
+            (InputAction::MoveLeft, KeyCode::Left),
+            (InputAction::MoveRight, KeyCode::Right),
+            (InputAction::SoftDrop, KeyCode::Down),
+            (InputAction::HardDrop, KeyCode::Up),
+            (InputAction::RotateCounterClockwise, KeyCode::Z),
+            (InputAction::RotateClockwise, KeyCode::X),
+            (InputAction::Hold, KeyCode::C),
+            (InputAction::Pause, KeyCode::Space),
+        ]))
+    }
+
+    pub fn wasd() -> Self {
+        Self(BTreeMap::from([
+            (InputAction::MoveLeft, KeyCode::A),
+            (InputAction::MoveRight, KeyCode::D),
+            (InputAction::SoftDrop, KeyCode::S),
+            (InputAction::HardDrop, KeyCode::W),
+            (InputAction::RotateCounterClockwise, KeyCode::Q),
+            (InputAction::RotateClockwise, KeyCode::E),
+            (InputAction::Hold, KeyCode::R),
+            (InputAction::Pause, KeyCode::Tab),
+        ]))
+    }
+
+    pub fn key(&self, action: InputAction) -> Option<KeyCode> {
+        self.0.get(&action).copied()
+    }
+
+    pub fn bind(&mut self, action: InputAction, key: KeyCode) {
+        self.0.insert(action, key);
+    }
+}
+
+// The button bound to each action, the same for every player's gamepad
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GamepadBindings(pub BTreeMap<InputAction, GamepadButtonType>);
+
+impl Default for GamepadBindings {
+    fn default() -> Self {
+        Self(BTreeMap::from([
+            (InputAction::MoveLeft, GamepadButtonType::DPadLeft),
+            (InputAction::MoveRight, GamepadButtonType::DPadRight),
+            (InputAction::SoftDrop, GamepadButtonType::DPadDown),
+            (InputAction::HardDrop, GamepadButtonType::DPadUp),
+            (InputAction::RotateCounterClockwise, GamepadButtonType::West),
+            (InputAction::RotateClockwise, GamepadButtonType::South),
+            (InputAction::Hold, GamepadButtonType::North),
+            (InputAction::Pause, GamepadButtonType::Start),
+        ]))
+    }
+}
+
+impl GamepadBindings {
+    pub fn button(&self, action: InputAction) -> Option<GamepadButtonType> {
+        self.0.get(&action).copied()
+    }
+}
+
+// How a player's held inputs repeat, in seconds unless noted
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handling {
+    // Delayed auto shift, how long a direction is held before it starts repeating
+    pub das: f32,
+    // Auto repeat rate, the time between repeats. Anything shorter than a frame repeats every frame
+    pub arr: f32,
+    // How many times faster than gravity the pill falls while soft drop is held
+    pub soft_drop_factor: f32,
+    pub hard_drop: bool,
+    // Shows where the pill will land
+    pub ghost_piece: bool,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: 0.15,
+            arr: 0.1,
+            soft_drop_factor: 10.0,
+            hard_drop: true,
+            ghost_piece: true,
+        }
+    }
+}
+
+// Bindings for every player, indexed by player number
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+pub struct InputMap {
+    pub players: Vec<KeyBindings>,
+    #[serde(default)]
+    pub gamepad: GamepadBindings,
+    #[serde(default)]
+    pub handling: Vec<Handling>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            players: vec![KeyBindings::arrows(), KeyBindings::wasd()],
+            gamepad: GamepadBindings::default(),
+            handling: vec![Handling::default(); 2],
+        }
+    }
+}
+
+impl InputMap {
+    // Players missing from the file get the default handling
+    pub fn handling(&self, number: usize) -> Handling {
+        self.handling.get(number).copied().unwrap_or_default()
+    }
+
+    pub fn handling_mut(&mut self, number: usize) -> &mut Handling {
+        if self.handling.len() <= number {
+            self.handling.resize(number + 1, Handling::default());
+        }
+        &mut self.handling[number]
+    }
+
+    pub fn player(&self, number: usize) -> Option<&KeyBindings> {
+        self.players.get(number)
+    }
+
+    pub fn player_mut(&mut self, number: usize) -> Option<&mut KeyBindings> {
+        self.players.get_mut(number)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        load_ron(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        save_ron(path, self)
+    }
+}
+
+pub(crate) fn load_input_map(
+    mut commands: Commands,
+) {
+    let path = input_map_path();
+    let input_map = if path.exists() {
+        InputMap::load(&path).unwrap_or_else(|err| {
+            warn!("Failed to load {}, using the default controls: {}", path.display(), err);
+            InputMap::default()
+        })
+    } else {
+        InputMap::default()
+    };
+    commands.insert_resource(input_map);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_map_round_trips_through_ron() {
+        let mut input_map = InputMap::default();
+        input_map.player_mut(1).unwrap().bind(InputAction::Hold, KeyCode::F);
+        input_map.handling_mut(0).ghost_piece = false;
+        input_map.handling_mut(1).das = 0.2;
+        let written = ron::ser::to_string_pretty(&input_map, ron::ser::PrettyConfig::default()).unwrap();
+        let read: InputMap = ron::from_str(&written).unwrap();
+        assert_eq!(read, input_map);
+    }
+
+    #[test]
+    fn test_missing_gamepad_and_handling_use_the_defaults() {
+        let written = ron::to_string(&InputMap::default().players).unwrap();
+        let read: InputMap = ron::from_str(&format!("(players: {})", written)).unwrap();
+        assert_eq!(read.players, InputMap::default().players);
+        assert_eq!(read.gamepad, GamepadBindings::default());
+        assert!(read.handling.is_empty());
+        assert_eq!(read.handling(1), Handling::default());
+    }
+}
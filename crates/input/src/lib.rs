@@ -1,26 +1,40 @@
 use bevy::prelude::*;
 use pills_core::*;
-use core::time::Duration;
 
-pub struct InputPlugin;
+pub use actions::*;
+pub use input_map::*;
+
+mod actions;
+mod input_map;
 
-#[derive(Component)]
-pub struct KeyControlled;
+pub struct InputPlugin;
 
-#[derive(Component, Deref, DerefMut)]
-struct MovementTimer(Timer);
+// Reads the actions of this player number, from their keys and gamepad
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct KeyControlled(pub usize);
 
-#[derive(Component, Deref, DerefMut)]
-struct DropTimer(Timer);
+// How long the current direction has been held, and how far into the next repeat it is
+#[derive(Component, Default)]
+struct AutoShift {
+    held: f32,
+    repeat: f32,
+}
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<PlayerActions>()
+            .init_resource::<GamepadAssignments>()
+            .add_systems(PreStartup, load_input_map)
+            .add_systems(PreUpdate, (assign_gamepads, update_player_actions)
+                .chain()
+                .after(bevy::input::InputSystem))
             .add_systems(OnEnter(GameState::Starting), setup_key_control)
             .add_systems(Update, (
                 handle_drop_input, 
                 handle_movement_input, 
                 handle_rotate_input,
+                handle_hold_input,
                 handle_pause_input,
             )
                     .run_if(in_state(GameState::Active))
@@ -31,46 +45,55 @@ impl Plugin for InputPlugin {
 
 fn setup_key_control(
     mut commands: Commands,
-    key_control_query: Query<(Entity, &BoardConfig)>,
+    key_control_query: Query<(Entity, &BoardConfig, Option<&KeyControlled>, Option<&RestoreBoard>)>,
+    input_map: Res<InputMap>,
 ) {
-    for (board, config) in key_control_query.iter() {
+    for (board, config, key_controlled, restore) in key_control_query.iter() {
         info!("Found a board with key control: {:?}", board);
         commands.entity(board)
-            .insert(MovementTimer(Timer::from_seconds(0.15, TimerMode::Repeating)))
-            .insert(DropTimer(Timer::from_seconds(config.drop_period, TimerMode::Repeating)));
+            .insert(AutoShift::default())
+            .insert(DropTimer::new(config, restore));
+        if key_controlled.is_some_and(|KeyControlled(player)| input_map.handling(*player).ghost_piece) {
+            commands.entity(board).insert(GhostPiece);
+        }
     }
 }
 
 fn handle_movement_input(
     mut commands: Commands,
-    mut movement_timer_query: Query<&mut MovementTimer, With<KeyControlled>>,
-    input: Res<Input<KeyCode>>,
+    mut auto_shift_query: Query<(&mut AutoShift, &KeyControlled)>,
+    actions: Res<PlayerActions>,
+    input_map: Res<InputMap>,
     time: Res<Time>,
     pivot_piece_query: Query<(Entity, &InBoard), With<PivotPiece>>,
 ) {
     for (piece_ent, board) in pivot_piece_query.iter() {
-        if let Ok(mut timer) = movement_timer_query.get_mut(**board) {
-            let (l, r) = (input.just_pressed(KeyCode::Left), input.just_pressed(KeyCode::Right));
+        if let Ok((mut shift, KeyControlled(player))) = auto_shift_query.get_mut(**board) {
+            let Some(state) = actions.player(*player) else { continue };
+            let handling = input_map.handling(*player);
+            let (l, r) = (state.just_pressed(InputAction::MoveLeft), state.just_pressed(InputAction::MoveRight));
             if (l || r) && !(l && r) {
-                timer.reset();
-                if l {
-                    commands.entity(piece_ent).insert(Move::Left);
-                } 
-                if r {
-                    commands.entity(piece_ent).insert(Move::Right);
-                }
+                *shift = AutoShift::default();
+                commands.entity(piece_ent).insert(if l { Move::Left } else { Move::Right });
+                continue;
             }
-            let (l, r) = (input.pressed(KeyCode::Left), input.pressed(KeyCode::Right));
-            if (l || r) && !(l && r) {
-                timer.tick(time.delta());
-                if timer.just_finished() {
-                    if l {
-                        commands.entity(piece_ent).insert(Move::Left);
-                    }
-                    if r {
-                        commands.entity(piece_ent).insert(Move::Right);
-                    }
+            let (l, r) = (state.pressed(InputAction::MoveLeft), state.pressed(InputAction::MoveRight));
+            if !(l || r) || (l && r) {
+                continue;
+            }
+            let held = shift.held;
+            shift.held += time.delta_seconds();
+            if held < handling.das {
+                // The first repeat lands as soon as the delay is over
+                if shift.held >= handling.das {
+                    commands.entity(piece_ent).insert(if l { Move::Left } else { Move::Right });
                 }
+                continue;
+            }
+            shift.repeat += time.delta_seconds();
+            if shift.repeat >= handling.arr {
+                shift.repeat = (shift.repeat - handling.arr).min(handling.arr);
+                commands.entity(piece_ent).insert(if l { Move::Left } else { Move::Right });
             }
         }
     }
@@ -78,25 +101,26 @@ fn handle_movement_input(
 
 fn handle_drop_input(
     mut commands: Commands,
-    mut drop_timer_query: Query<&mut DropTimer, With<KeyControlled>>,
-    input: Res<Input<KeyCode>>,
+    mut drop_timer_query: Query<(&mut DropTimer, &KeyControlled, &BoardConfig, Option<&SpeedCurve>)>,
+    actions: Res<PlayerActions>,
+    input_map: Res<InputMap>,
     time: Res<Time>,
     pivot_piece_query: Query<(Entity, &InBoard), With<PivotPiece>>,
 ) {
     for (piece_ent, board) in pivot_piece_query.iter() {
-        if let Ok(mut timer) = drop_timer_query.get_mut(**board) {
-            timer.tick(time.delta());
-            if input.just_pressed(KeyCode::Down) {
+        if let Ok((mut timer, KeyControlled(player), config, speed)) = drop_timer_query.get_mut(**board) {
+            let Some(state) = actions.player(*player) else { continue };
+            timer.update_period(config, speed);
+            let handling = input_map.handling(*player);
+            let speed = if state.pressed(InputAction::SoftDrop) { handling.soft_drop_factor.max(1.0) } else { 1.0 };
+            timer.tick(time.delta().mul_f32(speed));
+            if handling.hard_drop && state.just_pressed(InputAction::HardDrop) {
+                timer.reset();
+                commands.entity(piece_ent).insert(HardDrop);
+            } else if state.just_pressed(InputAction::SoftDrop) {
                 timer.reset();
                 commands.entity(piece_ent).insert(Drop);
-            } else if input.pressed(KeyCode::Down) {
-                if timer.just_finished() {
-                    commands.entity(piece_ent).insert(Drop);
-                    timer.reset();
-                }
-                timer.tick(Duration::from_secs_f32(0.1));
-            }
-            if timer.just_finished() {
+            } else if timer.just_finished() {
                 commands.entity(piece_ent).insert(Drop);
             }
         }
@@ -105,30 +129,47 @@ fn handle_drop_input(
 
 fn handle_rotate_input(
     mut commands: Commands,
-    key_control_query: Query<Entity, With<KeyControlled>>,
-    input: Res<Input<KeyCode>>,
+    key_control_query: Query<&KeyControlled>,
+    actions: Res<PlayerActions>,
     pivot_piece_query: Query<(Entity, &InBoard), With<PivotPiece>>,
 ) {
     for (piece_ent, board) in pivot_piece_query.iter() {
-        if key_control_query.get(**board).is_ok() {
-            if input.just_pressed(KeyCode::Z) {
+        if let Ok(KeyControlled(player)) = key_control_query.get(**board) {
+            let Some(state) = actions.player(*player) else { continue };
+            if state.just_pressed(InputAction::RotateCounterClockwise) {
                 commands.entity(piece_ent).insert(Rotate::Left);
-            } else if input.just_pressed(KeyCode::X) {
+            } else if state.just_pressed(InputAction::RotateClockwise) {
                 commands.entity(piece_ent).insert(Rotate::Right);
             }
         }
     }
 }
 
+fn handle_hold_input(
+    mut commands: Commands,
+    key_control_query: Query<&KeyControlled>,
+    actions: Res<PlayerActions>,
+    pivot_piece_query: Query<(Entity, &InBoard), With<PivotPiece>>,
+) {
+    for (piece_ent, board) in pivot_piece_query.iter() {
+        if let Ok(KeyControlled(player)) = key_control_query.get(**board) {
+            let Some(state) = actions.player(*player) else { continue };
+            if state.just_pressed(InputAction::Hold) {
+                commands.entity(piece_ent).insert(Hold);
+            }
+        }
+    }
+}
+
 fn handle_pause_input(
-    key_controlled_board: Query<Entity, With<KeyControlled>>,
+    key_controlled_board: Query<&KeyControlled>,
     mut game_state: ResMut<NextState<GameState>>,
-    input: Res<Input<KeyCode>>,
+    actions: Res<PlayerActions>,
 ) {
-    if key_controlled_board.is_empty() {
-        return
-    }
-    if input.just_pressed(KeyCode::Space) {
+    let paused = key_controlled_board.iter()
+        .filter_map(|KeyControlled(player)| actions.player(*player))
+        .any(|state| state.just_pressed(InputAction::Pause));
+    if paused {
         game_state.set(GameState::Paused);
     }
 }
\ No newline at end of file
@@ -3,13 +3,18 @@ use pills_core::*;
 use pills_input::*;
 use pills_augments::*;
 use rand::Rng;
-use rand::rngs::ThreadRng;
+
+pub use run_map::*;
+
+mod run_map;
 
 pub enum TerminalCondition {
     FirstWin,
     FirstLoss,
     LastRemaining,
     NoneRemaining,
+    // Whichever of FirstWin or FirstLoss happens first
+    FirstFinished,
 }
 
 pub struct LevelPlugin;
@@ -20,6 +25,8 @@ impl Plugin for LevelPlugin {
             .add_systems(Update, check_for_completion
                 .run_if(in_state(GameState::Active)))
             .add_systems(OnEnter(GameState::Finished), despawn_level)
+            // A suspended level is saved, so it goes away without finishing
+            .add_systems(OnTransition { from: GameState::Paused, to: GameState::NotStarted }, despawn_level)
         ;
     }
 }
@@ -63,6 +70,17 @@ pub struct LevelConfig {
     pub augments: Vec<Entity>,
 }
 
+impl Level {
+    // Boards still playing when another one decided the level get the opposite result
+    pub fn board_result(&self, board: Entity) -> Option<BoardFinished> {
+        match self.outcome {
+            Outcome::Win(winner) => Some(if winner == board { BoardFinished::Win } else { BoardFinished::Loss }),
+            Outcome::Loss(loser) => Some(if loser == board { BoardFinished::Loss } else { BoardFinished::Win }),
+            _ => None,
+        }
+    }
+}
+
 impl LevelConfig {
     pub fn with_budget(budget: u32) -> Self {
         Self {
@@ -78,9 +96,9 @@ impl LevelConfig {
         }
     }
 
-    pub fn add_random_augments(&mut self, commands: &mut Commands) -> &mut Self {
+    pub fn add_random_augments(&mut self, commands: &mut Commands, rng: &mut RunRng) -> &mut Self {
         while self.budget > 0 {
-            let augment = random_harmful_augment(&mut rand::thread_rng());
+            let augment = random_harmful_augment(&mut **rng);
             self.budget -= augment.cost();
             let id = commands.spawn_empty().add(augment).id();
             self.augments.push(id);
@@ -89,7 +107,7 @@ impl LevelConfig {
     }
 }
 
-fn random_config(difficulty: LevelDifficulty, rng: &mut ThreadRng) -> BoardConfig {
+fn random_config(difficulty: LevelDifficulty, rng: &mut impl Rng) -> BoardConfig {
     let mut config = BoardConfig::default();
     match difficulty {
         LevelDifficulty::Easy => {
@@ -108,18 +126,20 @@ fn random_config(difficulty: LevelDifficulty, rng: &mut ThreadRng) -> BoardConfi
     config
 }
 
-pub fn spawn_random_single_board_level(commands: &mut Commands, difficulty: LevelDifficulty, rng: &mut ThreadRng) -> Entity {
+pub fn spawn_random_single_board_level(commands: &mut Commands, difficulty: LevelDifficulty, rng: &mut RunRng) -> Entity {
     let board_entity = commands
         .spawn((
-            random_config(difficulty, rng),
-            KeyControlled,
+            random_config(difficulty, &mut **rng),
+            KeyControlled::default(),
+            BoardRng::from_run(rng),
+            SpeedCurve::default(),
         ))
         .id();
     commands.spawn_empty()
-        .add(random_harmful_augment(rng))
+        .add(random_harmful_augment(&mut **rng))
         .insert(InBoard(board_entity));
     commands.spawn_empty()
-        .add(random_helpful_augment(rng))
+        .add(random_helpful_augment(&mut **rng))
         .insert(InBoard(board_entity));
     commands
         .insert_resource(Level {
@@ -131,9 +151,9 @@ pub fn spawn_random_single_board_level(commands: &mut Commands, difficulty: Leve
     board_entity
 }
 
-pub fn spawn_single_board_level(commands: &mut Commands) -> Entity {
+pub fn spawn_single_board_level(commands: &mut Commands, rng: &mut RunRng) -> Entity {
     let board_entity = commands
-        .spawn((BoardConfig::default(), KeyControlled))
+        .spawn((BoardConfig::default(), KeyControlled::default(), BoardRng::from_run(rng), SpeedCurve::default()))
         .id();
     commands
         .insert_resource(Level {
@@ -145,9 +165,9 @@ pub fn spawn_single_board_level(commands: &mut Commands) -> Entity {
     board_entity
 }
 
-pub fn spawn_single_board_level_with_config(commands: &mut Commands, level_config: &LevelConfig) -> Entity {
+pub fn spawn_single_board_level_with_config(commands: &mut Commands, level_config: &LevelConfig, rng: &mut RunRng) -> Entity {
     let board_entity = commands
-        .spawn((BoardConfig::default(), KeyControlled))
+        .spawn((BoardConfig::default(), KeyControlled::default(), BoardRng::from_run(rng), SpeedCurve::default()))
         .id();
     for augment_id in &level_config.augments {
         commands.entity(*augment_id).insert(InBoard(board_entity));
@@ -158,6 +178,66 @@ pub fn spawn_single_board_level_with_config(commands: &mut Commands, level_confi
     board_entity
 }
 
+// Two boards with the same config and seed, indexed by player number
+pub fn spawn_versus_level(commands: &mut Commands, rng: &mut RunRng) -> [Entity; 2] {
+    let config = random_config(LevelDifficulty::Medium, &mut **rng);
+    let board_rng = BoardRng::from_run(rng);
+    let boards = [0, 1].map(|player| {
+        commands
+            .spawn((config.clone(), KeyControlled(player), board_rng.clone(), SpeedCurve::default()))
+            .id()
+    });
+    commands
+        .insert_resource(Level {
+            root: None,
+            // The second player's default keys are on the left of the keyboard, so their board is too
+            board_configs: vec![boards[1], boards[0]],
+            terminal_condition: TerminalCondition::FirstFinished,
+            outcome: Outcome::None,
+        });
+    boards
+}
+
+fn recorded_board_config(replay: &Replay) -> BoardConfig {
+    BoardConfig {
+        board_size: (replay.config.rows, replay.config.cols),
+        max_viruses: replay.config.max_viruses,
+        fall_period: replay.config.fall_period,
+        match_rules: replay.config.match_rules,
+        lock_delay: replay.config.lock_delay,
+        lock_moves: replay.config.lock_moves,
+        next_pills: replay.config.preview_count,
+        ..BoardConfig::default()
+    }
+}
+
+// A level that plays back a recorded board instead of being played
+pub fn spawn_replay_level(commands: &mut Commands, replay: Replay) -> Entity {
+    let board_config = recorded_board_config(&replay);
+    let board_entity = commands
+        .spawn((board_config, ReplayControlled::new(replay)))
+        .id();
+    let mut level = Level::default();
+    level.board_configs.push(board_entity);
+    commands.insert_resource(level);
+    board_entity
+}
+
+// A single board level picking up from where it was suspended
+pub fn spawn_restored_level(commands: &mut Commands, snapshot: BoardSnapshot) -> Entity {
+    let board_config = BoardConfig {
+        drop_period: snapshot.drop_period,
+        ..recorded_board_config(&snapshot.recording)
+    };
+    let board_entity = commands
+        .spawn((board_config, KeyControlled::default(), snapshot.speed.clone(), RestoreBoard(snapshot), Restored))
+        .id();
+    let mut level = Level::default();
+    level.board_configs.push(board_entity);
+    commands.insert_resource(level);
+    board_entity
+}
+
 fn despawn_level(
     mut commands: Commands,
     query: Query<Entity, With<InBoard>>,
@@ -194,6 +274,8 @@ fn check_for_completion(
         match (&level.terminal_condition, finished_boards.get(*entity)) {
             (FirstWin, Ok(Win)) => { finished += 1; winner = Some(*entity); },
             (FirstLoss, Ok(Loss)) => { finished += 1; loser = Some(*entity); },
+            (FirstFinished, Ok(Win)) => { finished += 1; winner = Some(*entity); },
+            (FirstFinished, Ok(Loss)) => { finished += 1; loser = Some(*entity); },
             (LastRemaining, Err(_)) => { unfinished += 1; winner = Some(*entity); },
             (NoneRemaining, Ok(_)) => { finished += 1; },
             (_, Err(_)) => { unfinished += 1; },
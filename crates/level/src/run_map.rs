@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::*;
+
+// Rows of nodes after the opening level, the last one is the boss
+pub const MAP_DEPTH: usize = 8;
+
+const MIN_ROW_WIDTH: usize = 2;
+const MAX_ROW_WIDTH: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Level,
+    // A level with more augments on it
+    Elite,
+    Shop,
+    // A breather, moving on without playing a level
+    Rest,
+    Boss,
+}
+
+impl NodeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Level => "Level",
+            NodeKind::Elite => "Elite",
+            NodeKind::Shop => "Shop",
+            NodeKind::Rest => "Rest",
+            NodeKind::Boss => "Boss",
+        }
+    }
+
+    pub fn is_level(&self) -> bool {
+        matches!(self, NodeKind::Level | NodeKind::Elite | NodeKind::Boss)
+    }
+
+    // Extra augment budget on top of the node's depth
+    fn budget_bonus(&self) -> u32 {
+        match self {
+            NodeKind::Elite => 2,
+            NodeKind::Boss => 4,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MapNode {
+    pub kind: NodeKind,
+    // Columns of the nodes in the next row this one leads to
+    pub next: Vec<usize>,
+}
+
+// Where a node is on the map, `depth` is its row
+#[derive(Clone, Copy, Component, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MapPosition {
+    pub depth: usize,
+    pub column: usize,
+}
+
+// The branching paths of a run, the player picks a node from the next row after each one
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+pub struct RunMap {
+    pub rows: Vec<Vec<MapNode>>,
+    // None until the first node is picked
+    pub current: Option<MapPosition>,
+}
+
+// Rest is at the top of the range, so cutting it off leaves the other kinds in the same proportion
+fn random_kind(rng: &mut impl Rng, allow_rest: bool) -> NodeKind {
+    match rng.gen_range(0..if allow_rest { 20 } else { 18 }) {
+        0..=10 => NodeKind::Level,
+        11..=14 => NodeKind::Elite,
+        15..=17 => NodeKind::Shop,
+        _ => NodeKind::Rest,
+    }
+}
+
+impl RunMap {
+    pub fn generate(rng: &mut RunRng) -> Self {
+        let widths: Vec<usize> = (0..MAP_DEPTH)
+            .map(|depth| if depth + 1 == MAP_DEPTH { 1 } else { rng.gen_range(MIN_ROW_WIDTH..=MAX_ROW_WIDTH) })
+            .collect();
+        // Always a chance to catch a breath before the boss
+        let rest_column = rng.gen_range(0..widths[MAP_DEPTH - 2]);
+        let mut rows: Vec<Vec<MapNode>> = widths.iter().enumerate()
+            .map(|(depth, width)| (0..*width).map(|column| MapNode {
+                kind: match depth {
+                    0 => NodeKind::Level,
+                    _ if depth + 1 == MAP_DEPTH => NodeKind::Boss,
+                    _ if depth + 2 == MAP_DEPTH && column == rest_column => NodeKind::Rest,
+                    _ => random_kind(&mut **rng, depth + 2 != MAP_DEPTH),
+                },
+                next: vec![],
+            }).collect())
+            .collect();
+
+        for depth in 0..MAP_DEPTH - 1 {
+            let (width, next_width) = (widths[depth], widths[depth + 1]);
+            // Each node leads to the one straight ahead of it and sometimes a neighbour
+            for (column, node) in rows[depth].iter_mut().enumerate() {
+                let ahead = column * next_width / width;
+                let mut next = vec![ahead];
+                let side = if rng.gen_bool(0.5) { ahead.checked_sub(1) } else { Some(ahead + 1) };
+                if let Some(side) = side.filter(|side| *side < next_width && rng.gen_bool(0.5)) {
+                    next.push(side);
+                }
+                node.next = next;
+            }
+            // and every node can be reached from the row before it
+            for next_column in 0..next_width {
+                if rows[depth].iter().any(|node| node.next.contains(&next_column)) {
+                    continue;
+                }
+                let column = next_column * width / next_width;
+                rows[depth][column].next.push(next_column);
+            }
+            for node in rows[depth].iter_mut() {
+                node.next.sort_unstable();
+                node.next.dedup();
+            }
+        }
+        Self { rows, current: None }
+    }
+
+    pub fn node(&self, position: MapPosition) -> Option<&MapNode> {
+        self.rows.get(position.depth).and_then(|row| row.get(position.column))
+    }
+
+    // The nodes the player can pick next
+    pub fn choices(&self) -> Vec<MapPosition> {
+        match self.current {
+            None => (0..self.rows.first().map_or(0, |row| row.len()))
+                .map(|column| MapPosition { depth: 0, column })
+                .collect(),
+            Some(current) => self.node(current).map_or(vec![], |node| node.next.iter()
+                .map(|column| MapPosition { depth: current.depth + 1, column: *column })
+                .collect()),
+        }
+    }
+
+    pub fn visit(&mut self, position: MapPosition) {
+        if self.choices().contains(&position) {
+            self.current = Some(position);
+        }
+    }
+
+    // The boss has been reached
+    pub fn finished(&self) -> bool {
+        self.current.is_some_and(|current| current.depth + 1 >= self.rows.len())
+    }
+
+    // A level for the node with harmful augments worth more the deeper it is, None for nodes without one
+    pub fn level_config(&self, position: MapPosition, commands: &mut Commands, rng: &mut RunRng) -> Option<LevelConfig> {
+        let kind = self.node(position)?.kind;
+        if !kind.is_level() {
+            return None;
+        }
+        let mut level_config = LevelConfig::with_budget(position.depth as u32 + 1 + kind.budget_bonus());
+        level_config.add_random_augments(commands, rng);
+        Some(level_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_maps_are_connected() {
+        for seed in 0..50 {
+            let map = RunMap::generate(&mut RunRng::resume(seed));
+            assert_eq!(map.rows.len(), MAP_DEPTH);
+            assert!(map.rows[0].iter().all(|node| node.kind == NodeKind::Level));
+            assert_eq!(map.rows[MAP_DEPTH - 1], vec![MapNode { kind: NodeKind::Boss, next: vec![] }]);
+            assert_eq!(map.rows[MAP_DEPTH - 2].iter().filter(|node| node.kind == NodeKind::Rest).count(), 1);
+
+            // Every node can be reached by walking forward from the first row
+            let mut reachable = vec![(0..map.rows[0].len()).collect::<Vec<usize>>()];
+            for depth in 0..MAP_DEPTH - 1 {
+                let next_width = map.rows[depth + 1].len();
+                let mut next: Vec<usize> = vec![];
+                for column in reachable[depth].iter() {
+                    let node = &map.rows[depth][*column];
+                    assert!(!node.next.is_empty());
+                    assert!(node.next.iter().all(|next_column| *next_column < next_width));
+                    next.extend(node.next.iter());
+                }
+                next.sort_unstable();
+                next.dedup();
+                assert_eq!(next, (0..next_width).collect::<Vec<usize>>(), "seed {}", seed);
+                reachable.push(next);
+            }
+        }
+    }
+
+    #[test]
+    fn test_finished_once_the_boss_is_visited() {
+        let mut map = RunMap::generate(&mut RunRng::resume(7));
+        assert_eq!(map.choices().len(), map.rows[0].len());
+        // Nodes that aren't one of the choices are ignored
+        map.visit(MapPosition { depth: 1, column: 0 });
+        assert_eq!(map.current, None);
+        for depth in 0..MAP_DEPTH {
+            assert!(!map.finished());
+            let choices = map.choices();
+            assert!(choices.iter().all(|choice| choice.depth == depth));
+            map.visit(choices[0]);
+            assert_eq!(map.current, Some(choices[0]));
+        }
+        assert!(map.finished());
+        assert!(map.choices().is_empty());
+    }
+}
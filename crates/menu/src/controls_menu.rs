@@ -0,0 +1,273 @@
+use bevy::prelude::*;
+use super::*;
+
+pub(crate) struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::ControlsMenu), spawn_controls)
+            .add_systems(Update, (
+                handle_interactions,
+                handle_binding_interactions,
+                capture_binding,
+                handle_gamepad_assignment,
+                handle_ghost_piece_toggle,
+            ).run_if(in_state(AppState::ControlsMenu)))
+            .add_systems(OnExit(AppState::ControlsMenu), (despawn, stop_capture))
+        ;
+    }
+}
+
+#[derive(Component)]
+struct BindingButton {
+    player: usize,
+    action: InputAction,
+}
+
+// Cycles the player's gamepad through the connected ones
+#[derive(Component)]
+struct GamepadAssignButton {
+    player: usize,
+}
+
+// Turns the player's ghost piece on or off
+#[derive(Component)]
+struct GhostPieceButton {
+    player: usize,
+}
+
+// The binding waiting for the next key press
+#[derive(Resource)]
+struct AwaitingKey(Entity);
+
+fn binding_text(action: InputAction, key: Option<KeyCode>) -> String {
+    match key {
+        Some(key) => format!("{}: {:?}", action.name(), key),
+        None => format!("{}: -", action.name()),
+    }
+}
+
+fn gamepad_text(gamepad: Option<Gamepad>) -> String {
+    match gamepad {
+        Some(gamepad) => format!("Gamepad: {}", gamepad.id + 1),
+        None => "Gamepad: None".to_string(),
+    }
+}
+
+fn save_input_map(input_map: &InputMap) {
+    let path = input_map_path();
+    if let Err(err) = input_map.save(&path) {
+        warn!("Failed to save controls to {}: {}", path.display(), err);
+    }
+}
+
+fn ghost_piece_text(shown: bool) -> String {
+    format!("Ghost Piece: {}", if shown { "On" } else { "Off" })
+}
+
+fn spawn_controls(
+    mut commands: Commands,
+    input_map: Res<InputMap>,
+    assignments: Res<GamepadAssignments>,
+) {
+    let mut footer_id = None;
+    let root_entity = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()},
+            background_color: Color::BLACK.into(),
+            ..default()})
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section("Controls", text_style(80.0, Color::WHITE)));
+
+            // A column of bindings for each player
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            }).with_children(|builder| {
+                for (player, keys) in input_map.players.iter().enumerate() {
+                    builder.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            margin: UiRect::all(Val::Px(16.0)),
+                            ..default()
+                        },
+                        ..default()
+                    }).with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(format!("Player {}", player + 1), text_style(40.0, Color::WHITE)));
+                        for action in InputAction::ALL {
+                            builder.spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::vertical(Val::Px(4.0)),
+                                        ..default()
+                                    },
+                                    background_color: Color::BLACK.into(),
+                                    ..default()
+                                },
+                                BindingButton { player, action },
+                            )).with_children(|builder| {
+                                builder.spawn(TextBundle::from_section(binding_text(action, keys.key(action)), text_style(28.0, Color::WHITE)));
+                            });
+                        }
+                        builder.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::vertical(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: Color::BLACK.into(),
+                                ..default()
+                            },
+                            GamepadAssignButton { player },
+                        )).with_children(|builder| {
+                            builder.spawn(TextBundle::from_section(gamepad_text(assignments.gamepad(player)), text_style(28.0, Color::WHITE)));
+                        });
+                        builder.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::vertical(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: Color::BLACK.into(),
+                                ..default()
+                            },
+                            GhostPieceButton { player },
+                        )).with_children(|builder| {
+                            builder.spawn(TextBundle::from_section(ghost_piece_text(input_map.handling(player).ghost_piece), text_style(28.0, Color::WHITE)));
+                        });
+                    });
+                }
+            });
+
+            footer_id = builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            })
+            .id().into();
+        })
+        .id();
+    commands.spawn(MenuOption::Back)
+        .add(MenuOptionUI)
+        .set_parent(footer_id.unwrap());
+    commands.insert_resource(MenuData { root_entity });
+}
+
+fn handle_binding_interactions(
+    mut commands: Commands,
+    buttons: Query<(Entity, &Interaction, &BindingButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    awaiting: Option<Res<AwaitingKey>>,
+) {
+    for (id, interaction, binding, children) in buttons.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed if awaiting.is_none() => {
+                text.sections[0].value = format!("{}: press a key", binding.action.name());
+                text.sections[0].style.color = Color::PINK;
+                commands.insert_resource(AwaitingKey(id));
+            },
+            Interaction::Hovered if awaiting.is_none() => {
+                text.sections[0].style.color = hover_color(interaction);
+            },
+            Interaction::None if awaiting.as_ref().is_none_or(|awaiting| awaiting.0 != id) => {
+                text.sections[0].style.color = hover_color(interaction);
+            },
+            _ => {},
+        }
+    }
+}
+
+fn capture_binding(
+    mut commands: Commands,
+    mut input_map: ResMut<InputMap>,
+    input: Res<Input<KeyCode>>,
+    awaiting: Option<Res<AwaitingKey>>,
+    buttons: Query<(&BindingButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let Some(awaiting) = awaiting else { return };
+    let Some(key) = input.get_just_pressed().next().copied() else { return };
+    commands.remove_resource::<AwaitingKey>();
+    let Ok((binding, children)) = buttons.get(awaiting.0) else { return };
+    if let Some(keys) = input_map.player_mut(binding.player) {
+        keys.bind(binding.action, key);
+    }
+    save_input_map(&input_map);
+    if let Ok(mut text) = texts.get_mut(children[0]) {
+        text.sections[0].value = binding_text(binding.action, Some(key));
+        text.sections[0].style.color = Color::WHITE;
+    }
+}
+
+fn handle_gamepad_assignment(
+    buttons: Query<(&Interaction, &GamepadAssignButton, &Children), Changed<Interaction>>,
+    all_buttons: Query<(&GamepadAssignButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    mut assignments: ResMut<GamepadAssignments>,
+    gamepads: Res<Gamepads>,
+) {
+    let mut reassigned = false;
+    for (interaction, button, children) in buttons.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed => {
+                let mut connected: Vec<Gamepad> = gamepads.iter().collect();
+                connected.sort_by_key(|gamepad| gamepad.id);
+                let current = assignments.gamepad(button.player);
+                let next = match current.and_then(|current| connected.iter().position(|gamepad| *gamepad == current)) {
+                    Some(index) => connected.get(index + 1).copied(),
+                    None => connected.first().copied(),
+                };
+                assignments.assign(button.player, next);
+                reassigned = true;
+            },
+            Interaction::Hovered | Interaction::None => text.sections[0].style.color = hover_color(interaction),
+        }
+    }
+    // Taking a gamepad can take it away from another player, so refresh all of them
+    if reassigned {
+        for (button, children) in all_buttons.iter() {
+            if let Ok(mut text) = texts.get_mut(children[0]) {
+                text.sections[0].value = gamepad_text(assignments.gamepad(button.player));
+            }
+        }
+    }
+}
+
+fn handle_ghost_piece_toggle(
+    buttons: Query<(&Interaction, &GhostPieceButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut input_map: ResMut<InputMap>,
+) {
+    for (interaction, button, children) in buttons.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed => {
+                let handling = input_map.handling_mut(button.player);
+                handling.ghost_piece = !handling.ghost_piece;
+                text.sections[0].value = ghost_piece_text(handling.ghost_piece);
+                save_input_map(&input_map);
+            },
+            Interaction::Hovered | Interaction::None => text.sections[0].style.color = hover_color(interaction),
+        }
+    }
+}
+
+fn stop_capture(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<AwaitingKey>();
+}
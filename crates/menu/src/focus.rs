@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use super::*;
+
+pub(crate) struct MenuFocusPlugin;
+
+impl Plugin for MenuFocusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MenuFocus>()
+            .add_systems(Update, navigate_menu_focus.run_if(not(in_state(AppState::InGame))))
+        ;
+    }
+}
+
+// The button a gamepad has moved to, pressing the confirm button clicks it
+#[derive(Default, Resource)]
+struct MenuFocus(Option<Entity>);
+
+fn set_text_color(texts: &mut Query<&mut Text>, children: &Children, color: Color) {
+    if let Some(mut text) = children.first().and_then(|child| texts.get_mut(*child).ok()) {
+        text.sections[0].style.color = color;
+    }
+}
+
+fn navigate_menu_focus(
+    mut focus: ResMut<MenuFocus>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut buttons: Query<(Entity, &GlobalTransform, &mut Interaction, &Children), With<Button>>,
+    mut texts: Query<&mut Text>,
+) {
+    let just_pressed = |button_type| gamepads.iter()
+        .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)));
+    let mut step = 0;
+    if just_pressed(GamepadButtonType::DPadDown) || just_pressed(GamepadButtonType::DPadRight) {
+        step += 1;
+    }
+    if just_pressed(GamepadButtonType::DPadUp) || just_pressed(GamepadButtonType::DPadLeft) {
+        step -= 1;
+    }
+
+    // Top to bottom, then left to right, the way the menus read
+    let mut order: Vec<(Entity, Vec3)> = buttons.iter()
+        .map(|(entity, transform, _, _)| (entity, transform.translation()))
+        .collect();
+    order.sort_by(|(_, l), (_, r)| l.y.total_cmp(&r.y).then(l.x.total_cmp(&r.x)));
+    let current = focus.0.and_then(|focused| order.iter().position(|(entity, _)| *entity == focused));
+    if current.is_none() {
+        focus.0 = None;
+    }
+
+    if step != 0 && !order.is_empty() {
+        let next = match current {
+            Some(index) => (index as isize + step).rem_euclid(order.len() as isize) as usize,
+            None => 0,
+        };
+        if let Some(Ok((_, _, _, children))) = focus.0.map(|focused| buttons.get(focused)) {
+            set_text_color(&mut texts, children, Color::WHITE);
+        }
+        let next = order[next].0;
+        if let Ok((_, _, _, children)) = buttons.get(next) {
+            set_text_color(&mut texts, children, Color::YELLOW);
+        }
+        focus.0 = Some(next);
+    }
+
+    if just_pressed(GamepadButtonType::South) {
+        if let Some(Ok((_, _, mut interaction, _))) = focus.0.map(|focused| buttons.get_mut(focused)) {
+            *interaction = Interaction::Pressed;
+        }
+    }
+}
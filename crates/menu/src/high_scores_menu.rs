@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use super::*;
+
+pub(crate) struct HighScoreMenuPlugin;
+
+impl Plugin for HighScoreMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::HighScoreMenu), spawn_high_scores)
+            .add_systems(Update, (
+                handle_interactions,
+                handle_mode_interactions,
+            ).run_if(in_state(AppState::HighScoreMenu)))
+            .add_systems(OnExit(AppState::HighScoreMenu), despawn)
+        ;
+    }
+}
+
+// Shows the table for this mode when pressed
+#[derive(Component)]
+struct ModeButton(GameMode);
+
+// The node holding the rows of the table being shown
+#[derive(Component)]
+struct HighScoreRows;
+
+fn spawn_rows(builder: &mut ChildBuilder, high_scores: &HighScores, mode: GameMode) {
+    builder.spawn(TextBundle::from_section(mode.name(), text_style(40.0, Color::WHITE)));
+    let mut empty = true;
+    for (rank, entry) in high_scores.scores(mode).enumerate() {
+        empty = false;
+        let mut row = format!("{:>2}. {:>8}  {}", rank + 1, entry.score, entry.day());
+        if mode == GameMode::Single {
+            row += &format!("  Levels: {}  Difficulty: {}", entry.levels_cleared, entry.difficulty);
+            if !entry.augments.is_empty() {
+                row += &format!("  {}", entry.augments.join(", "));
+            }
+        }
+        builder.spawn(TextBundle::from_section(row, text_style(24.0, Color::WHITE)));
+    }
+    if empty {
+        builder.spawn(TextBundle::from_section("No scores yet", text_style(24.0, Color::WHITE)));
+    }
+}
+
+fn spawn_high_scores(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+) {
+    let mut footer_id = None;
+    let root_entity = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()},
+            background_color: Color::BLACK.into(),
+            ..default()})
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section("High Scores", text_style(80.0, Color::WHITE)));
+
+            // A button for each mode's table
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            }).with_children(|builder| {
+                for mode in GameMode::ALL {
+                    builder.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                margin: UiRect::horizontal(Val::Px(16.0)),
+                                padding: UiRect::vertical(Val::Px(4.0)),
+                                ..default()
+                            },
+                            background_color: Color::BLACK.into(),
+                            ..default()
+                        },
+                        ModeButton(mode),
+                    )).with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(mode.name(), text_style(28.0, Color::WHITE)));
+                    });
+                }
+            });
+
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::all(Val::Px(16.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HighScoreRows,
+            )).with_children(|builder| {
+                spawn_rows(builder, &high_scores, GameMode::Single);
+            });
+
+            footer_id = builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            })
+            .id().into();
+        })
+        .id();
+    commands.spawn(MenuOption::Back)
+        .add(MenuOptionUI)
+        .set_parent(footer_id.unwrap());
+    commands.insert_resource(MenuData { root_entity });
+}
+
+fn handle_mode_interactions(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &ModeButton, &Children), Changed<Interaction>>,
+    rows: Query<Entity, With<HighScoreRows>>,
+    mut texts: Query<&mut Text>,
+    high_scores: Res<HighScores>,
+) {
+    for (interaction, button, children) in buttons.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed => {
+                for rows_id in rows.iter() {
+                    commands.entity(rows_id)
+                        .despawn_descendants()
+                        .with_children(|builder| spawn_rows(builder, &high_scores, button.0));
+                }
+            },
+            Interaction::Hovered | Interaction::None => text.sections[0].style.color = hover_color(interaction),
+        }
+    }
+}
@@ -1,16 +1,44 @@
 use bevy::prelude::*;
 use super::*;
 
-#[derive(Default, Deref, DerefMut, Resource)]
-struct FinishedCount(u32);
+// Points of score it takes to earn a coin to spend in the shop
+pub(crate) const SCORE_PER_COIN: usize = 5;
+
+// What the current run has done so far, kept for its high score
+#[derive(Default, Resource)]
+pub(crate) struct RunHistory {
+    pub levels_cleared: u32,
+    pub difficulty: u32,
+    pub augments: Vec<String>,
+    // Coins spent in the shop
+    pub spent: u32,
+    // Augments bought in the shop, added to every level after
+    pub purchased: Vec<SavedAugment>,
+}
+
+impl RunHistory {
+    pub fn coins(&self, score: usize) -> u32 {
+        ((score / SCORE_PER_COIN) as u32).saturating_sub(self.spent)
+    }
+
+    fn high_score(&self, mode: GameMode, score: usize) -> HighScore {
+        HighScore {
+            difficulty: self.difficulty,
+            levels_cleared: self.levels_cleared,
+            augments: self.augments.clone(),
+            ..HighScore::new(mode, score)
+        }
+    }
+}
 
 pub(crate) struct LevelMenuPlugin;
 
 impl Plugin for LevelMenuPlugin {
     fn build(&self, app: &mut App) {
         app
-            .init_resource::<FinishedCount>()
-            .add_systems(OnEnter(GameState::Finished), (setup, setup_resources))
+            .init_resource::<RunHistory>()
+            .add_systems(Startup, setup_resources)
+            .add_systems(OnEnter(GameState::Finished), setup)
             .add_systems(Update, (handle_interactions, add_icons).run_if(in_state(AppState::LevelMenu)))
             .add_systems(OnEnter(AppState::LevelMenu), spawn)
             .add_systems(OnExit(AppState::LevelMenu), despawn)
@@ -22,6 +50,10 @@ impl Plugin for LevelMenuPlugin {
 pub(crate) struct SelectedLevelConfig(pub Entity);
 
 pub(crate) fn add_level_button_bundle_with_icons(world: &mut World, id: Entity) {
+    // Levels on the run map are labelled with the kind of node they are
+    let label = world.get::<MapPosition>(id)
+        .and_then(|position| world.get_resource::<RunMap>()?.node(*position))
+        .map_or("Play!", |node| node.kind.name());
     world.entity_mut(id)
         .insert(
             NodeBundle {
@@ -52,7 +84,7 @@ pub(crate) fn add_level_button_bundle_with_icons(world: &mut World, id: Entity)
             ))
             .with_children(|parent| {
                 parent.spawn(TextBundle::from_section(
-                    "Play!",
+                    label,
                     TextStyle {
                         font_size: 40.0,
                         color: Color::WHITE.into(),
@@ -64,7 +96,7 @@ pub(crate) fn add_level_button_bundle_with_icons(world: &mut World, id: Entity)
 }
 
 #[derive(Resource)]
-struct IconAtlasHandle(Handle<TextureAtlas>);
+pub(crate) struct IconAtlasHandle(Handle<TextureAtlas>);
 
 fn setup_resources(
     mut commands: Commands,
@@ -77,7 +109,7 @@ fn setup_resources(
     commands.insert_resource(IconAtlasHandle(texture_atlas_handle));
 }
 
-fn add_icons(
+pub(crate) fn add_icons(
     mut commands: Commands,
     icons: Res<IconAtlasHandle>,
     menu_options: Query<(Entity, &Parent, &SelectedLevelConfig), (Added<MenuOption>, Added<SelectedLevelConfig>)>,
@@ -124,52 +156,112 @@ fn add_icons(
     }
 }
 
-#[derive(Component)]
-struct LastOption;
-
 fn setup(
     mut commands: Commands,
-    mut game_state: ResMut<NextState<GameState>>,
-    mut app_state: ResMut<NextState<AppState>>,
-    mut finished_count: ResMut<FinishedCount>,
-    boards: Query<(&BoardFinished, &BoardPlayer), With<BoardPlayer>>,
-    scores: Query<&GlobalScore, With<Player>>,
+    mut states: NextStates,
+    mut high_scores: ResMut<HighScores>,
+    boards: Query<(Option<&BoardFinished>, &BoardPlayer, Has<AiControlled>)>,
+    mut run: CurrentRun,
+    level: Res<Level>,
+    map: Option<Res<RunMap>>,
 ) {
-    let (result, player) = boards.single();
+    let results: Vec<(Option<BoardFinished>, Entity, bool)> = level.board_configs.iter()
+        .filter_map(|board| boards.get(*board).ok().map(|(finished, player, computer)| {
+            (level.board_result(*board).or(finished.copied()), player.0, computer)
+        }))
+        .collect();
+    if results.len() > 1 {
+        spawn_versus_results(&mut commands, &results, &run.players, &mut high_scores);
+        commands.spawn(MenuTitle::Custom(format!("Seed: {}", run.seed.0)));
+        states.set(AppState::LevelMenu, GameState::NotStarted);
+        return;
+    }
+    let Some(&(Some(result), player, _)) = results.first() else { return };
+    let score = run.players.get(player).ok().and_then(|(_, score)| score).map(|score| score.0);
     match result {
         BoardFinished::Win => {
-            **finished_count += 1;
-            commands.spawn(MenuTitle::Victory);
-            // Two random configs
-            for _ in 0..2 {
-                let mut level_config = LevelConfig::with_budget(**finished_count);
-                level_config.add_random_augments(&mut commands);
-                commands.spawn((MenuOption::SpecificLevel, level_config));
+            run.history.levels_cleared += 1;
+            let map = map.map_or_else(|| RunMap::generate(&mut run.rng), |map| map.clone());
+            if !map.finished() {
+                // On to the next node of the run
+                commands.insert_resource(map);
+                states.set(AppState::RunMap, GameState::NotStarted);
+                return;
             }
-            // One specific config
-            let explosive = Volatility { 
-                area: AreaOfEffect::Radius(2), 
-                filter: red_viruses, 
-            };
-            let explosive_id = commands.spawn_empty().add(Augment::Volatility(explosive)).id();
-            let frequency = Frequency { amount: 10 };
-            let frequency_id = commands.spawn_empty().add(Augment::Frequency(frequency)).id();
-            let level_config = LevelConfig::with_augments(vec![explosive_id, frequency_id]);
-            commands.spawn((MenuOption::SpecificLevel, level_config));
-            commands.spawn((MenuOption::Exit, LastOption));
+            commands.spawn(MenuTitle::Victory);
+            commands.spawn(MenuTitle::Custom("Run Complete".to_string()));
+            record_high_score(&mut commands, &mut high_scores, &run.history, score);
+            end_run(&mut commands, &mut run.history);
         },
         BoardFinished::Loss => {
-            **finished_count = 0;
             commands.spawn(MenuTitle::GameOver);
-            commands.spawn_batch([
-                (MenuOption::Play),
-                (MenuOption::Exit)
-            ]);
+            record_high_score(&mut commands, &mut high_scores, &run.history, score);
+            end_run(&mut commands, &mut run.history);
         },
     }
-    if let Ok(score) = scores.get(player.0) {
-        commands.spawn(MenuTitle::Custom(format!("Score: {}", score.0).to_string()));
+    if let Some(score) = score {
+        commands.spawn(MenuTitle::Custom(format!("Score: {}", score)));
+    }
+    commands.spawn(MenuTitle::Custom(format!("Seed: {}", run.seed.0)));
+    states.set(AppState::LevelMenu, GameState::NotStarted);
+}
+
+fn record_high_score(
+    commands: &mut Commands,
+    high_scores: &mut HighScores,
+    history: &RunHistory,
+    score: Option<usize>,
+) {
+    let entry = history.high_score(GameMode::Single, score.unwrap_or(0));
+    match high_scores.record(entry) {
+        Some(0) => { commands.spawn(MenuTitle::Custom("New High Score!".to_string())); },
+        Some(rank) => { commands.spawn(MenuTitle::Custom(format!("High Score #{}", rank + 1))); },
+        None => {},
+    }
+}
+
+// Nothing is left of the run to continue, only a new one can be started
+fn end_run(commands: &mut Commands, history: &mut RunHistory) {
+    *history = RunHistory::default();
+    remove_run_save();
+    commands.remove_resource::<RunMap>();
+    commands.spawn_batch([
+        (MenuOption::Play),
+        (MenuOption::Exit)
+    ]);
+}
+
+fn spawn_versus_results(
+    commands: &mut Commands,
+    results: &[(Option<BoardFinished>, Entity, bool)],
+    players: &Query<(&Player, Option<&GlobalScore>)>,
+    high_scores: &mut HighScores,
+) {
+    let name = |player: &Player, computer: bool| if computer { "Computer".to_string() } else { format!("Player {}", player.0 + 1) };
+    let winner = results.iter()
+        .find(|(result, _, _)| *result == Some(BoardFinished::Win))
+        .and_then(|(_, player, computer)| players.get(*player).ok().map(|(player, _)| name(player, *computer)));
+    match winner {
+        Some(winner) => commands.spawn(MenuTitle::Custom(format!("{} Wins", winner))),
+        None => commands.spawn(MenuTitle::Custom("Draw".to_string())),
+    };
+    let against_computer = results.iter().any(|(_, _, computer)| *computer);
+    for (result, player, computer) in results {
+        let Ok((player, score)) = players.get(*player) else { continue };
+        let result = match result {
+            Some(BoardFinished::Win) => "Win",
+            Some(BoardFinished::Loss) => "Loss",
+            None => "Draw",
+        };
+        let score = score.map_or(0, |score| score.0);
+        // Only people get onto the high score table
+        if !computer {
+            high_scores.record(HighScore::new(GameMode::Versus, score));
+        }
+        commands.spawn(MenuTitle::Custom(format!("{}: {} - Score: {}", name(player, *computer), result, score)));
     }
-    game_state.set(GameState::NotStarted);
-    app_state.set(AppState::LevelMenu);
+    commands.spawn_batch([
+        (if against_computer { MenuOption::VersusComputer } else { MenuOption::Versus }),
+        (MenuOption::Exit)
+    ]);
 }
@@ -5,16 +5,30 @@ use pills_core::*;
 use pills_level::*;
 use pills_score::*;
 use pills_augments::*;
+use pills_input::*;
+use pills_ai::*;
 
 use menu::*;
 use main_menu::*;
 use pause_menu::*;
 use level_menu::*;
+use controls_menu::*;
+use high_scores_menu::*;
+use run_map_menu::*;
+use shop_menu::*;
+use run_save::*;
+use focus::*;
 
 mod menu;
 mod main_menu;
 mod pause_menu;
 mod level_menu;
+mod controls_menu;
+mod high_scores_menu;
+mod run_map_menu;
+mod shop_menu;
+mod run_save;
+mod focus;
 
 pub struct MenuPluginGroup;
 
@@ -24,6 +38,12 @@ impl PluginGroup for MenuPluginGroup {
             .add(LevelMenuPlugin)
             .add(MainMenuPlugin)
             .add(PauseMenuPlugin)
+            .add(ControlsMenuPlugin)
+            .add(HighScoreMenuPlugin)
+            .add(RunMapMenuPlugin)
+            .add(ShopMenuPlugin)
+            .add(MenuOptionsPlugin)
+            .add(MenuFocusPlugin)
     }
 }
 
@@ -6,9 +6,8 @@ pub(crate) struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(Startup, setup)
             .add_systems(Update, handle_interactions.run_if(in_state(AppState::MainMenu)))
-            .add_systems(OnEnter(AppState::MainMenu), spawn)
+            .add_systems(OnEnter(AppState::MainMenu), (setup, apply_deferred, spawn).chain())
             .add_systems(OnExit(AppState::MainMenu), despawn)
         ;
     }
@@ -20,8 +19,15 @@ fn setup(
     commands.spawn(
         MenuTitle::Custom("Pills".to_string())
     );
+    if run_save_path().exists() {
+        commands.spawn(MenuOption::Continue);
+    }
     commands.spawn_batch([
         (MenuOption::Play),
+        (MenuOption::Versus),
+        (MenuOption::VersusComputer),
+        (MenuOption::Controls),
+        (MenuOption::HighScores),
         (MenuOption::Exit),
     ]);
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
 use pills_core::*;
 use super::*;
 
@@ -11,8 +12,15 @@ pub(crate) enum MenuTitle {
 
 #[derive(Clone, Component)]
 pub(crate) enum MenuOption {
+    Continue,
     Play,
+    Versus,
+    VersusComputer,
     SpecificLevel,
+    Controls,
+    HighScores,
+    Suspend,
+    Back,
     Exit,
 }
 
@@ -75,7 +83,7 @@ pub(crate) fn spawn(
     }
     for (id, option) in &menu_options {
         match option {
-            MenuOption::Play | MenuOption::Exit => {
+            MenuOption::Continue | MenuOption::Play | MenuOption::Versus | MenuOption::VersusComputer | MenuOption::Controls | MenuOption::HighScores | MenuOption::Suspend | MenuOption::Back | MenuOption::Exit => {
                 commands.entity(id)
                     .add(MenuOptionUI)
                     .set_parent(footer_id.unwrap());
@@ -90,44 +98,203 @@ pub(crate) fn spawn(
     commands.insert_resource(MenuData { root_entity });
 }
 
+// The options that start or continue a level, the same wherever their buttons are shown
+pub(crate) struct MenuOptionsPlugin;
+
+impl Plugin for MenuOptionsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (
+                start_new_run,
+                continue_saved_run,
+                start_versus,
+                start_specific_level,
+            ).run_if(not(in_state(AppState::InGame))))
+        ;
+    }
+}
+
+// Where a menu goes next, the app and game states usually change together
+#[derive(SystemParam)]
+pub(crate) struct NextStates<'w> {
+    pub app: ResMut<'w, NextState<AppState>>,
+    pub game: ResMut<'w, NextState<GameState>>,
+}
+
+impl NextStates<'_> {
+    pub fn set(&mut self, app: AppState, game: GameState) {
+        self.app.set(app);
+        self.game.set(game);
+    }
+}
+
+// The level config a level button was made for, and the names of its augments
+#[derive(SystemParam)]
+pub(crate) struct SelectedLevel<'w, 's> {
+    selected: Query<'w, 's, &'static SelectedLevelConfig>,
+    configs: Query<'w, 's, &'static LevelConfig>,
+    names: Query<'w, 's, &'static Name>,
+}
+
+impl SelectedLevel<'_, '_> {
+    fn config(&self, button: Entity) -> Option<&LevelConfig> {
+        // TODO the level config is not on the button entity because of the heirarchy
+        let config_id = self.selected.get(button).ok()?;
+        self.configs.get(config_id.0).ok()
+    }
+
+    fn augment_names(&self, level_config: &LevelConfig) -> Vec<String> {
+        level_config.augments.iter()
+            .filter_map(|augment| self.names.get(*augment).ok())
+            .map(|name| name.to_string())
+            .collect()
+    }
+}
+
+type MenuButtonFilter = (Changed<Interaction>, With<Button>);
+
+type PressedOptions<'w, 's> = Query<'w, 's, (Entity, &'static Interaction, &'static MenuOption), MenuButtonFilter>;
+
+fn start_new_run(
+    mut commands: Commands,
+    options: PressedOptions,
+    mut states: NextStates,
+    curr_game_state: Res<State<GameState>>,
+    player_query: Query<(Entity, &Player)>,
+    fixed_seed: Option<Res<FixedSeed>>,
+    mut history: ResMut<RunHistory>,
+) {
+    for (_, interaction, option) in &options {
+        if !matches!((interaction, option), (Interaction::Pressed, MenuOption::Play)) {
+            continue;
+        }
+        match curr_game_state.get() {
+            GameState::Finished | GameState::NotStarted => {
+                let Some(player_ent) = find_player(&player_query, 0) else { continue };
+                let mut rng = start_run(&mut commands, fixed_seed.as_deref());
+                // A new run starts the score over, it is added back when the board starts
+                *history = RunHistory::default();
+                commands.entity(player_ent).remove::<GlobalScore>();
+                commands.remove_resource::<RunMap>();
+                remove_run_save();
+                let board_ent = spawn_single_board_level(&mut commands, &mut rng);
+                commands.entity(board_ent).insert(BoardPlayer(player_ent));
+                commands.insert_resource(rng);
+                states.game.set(GameState::Starting);
+            },
+            GameState::Paused => states.game.set(GameState::Active),
+            _ => {},
+        }
+        states.app.set(AppState::InGame);
+    }
+}
+
+fn continue_saved_run(
+    mut commands: Commands,
+    options: PressedOptions,
+    mut states: NextStates,
+    player_query: Query<(Entity, &Player)>,
+) {
+    for (_, interaction, option) in &options {
+        if !matches!((interaction, option), (Interaction::Pressed, MenuOption::Continue)) {
+            continue;
+        }
+        let Some(player_ent) = find_player(&player_query, 0) else { continue };
+        match RunSave::load(run_save_path()) {
+            Ok(run) => {
+                continue_run(&mut commands, &run, player_ent);
+                if run.level.is_some() {
+                    states.set(AppState::InGame, GameState::Starting);
+                } else {
+                    states.app.set(AppState::RunMap);
+                }
+            },
+            Err(err) => {
+                warn!("Failed to load the saved run: {}", err);
+                remove_run_save();
+            },
+        }
+    }
+}
+
+fn start_versus(
+    mut commands: Commands,
+    options: PressedOptions,
+    mut states: NextStates,
+    player_query: Query<(Entity, &Player)>,
+    fixed_seed: Option<Res<FixedSeed>>,
+) {
+    for (_, interaction, option) in &options {
+        if !matches!((interaction, option), (Interaction::Pressed, MenuOption::Versus | MenuOption::VersusComputer)) {
+            continue;
+        }
+        let mut rng = start_run(&mut commands, fixed_seed.as_deref());
+        let boards = spawn_versus_level(&mut commands, &mut rng);
+        for (number, board_ent) in boards.into_iter().enumerate() {
+            let player_ent = find_player(&player_query, number)
+                .unwrap_or_else(|| commands.spawn(Player(number)).id());
+            commands.entity(player_ent).remove::<GlobalScore>();
+            commands.entity(board_ent).insert(BoardPlayer(player_ent));
+        }
+        if matches!(option, MenuOption::VersusComputer) {
+            // The second player's keys are handed over to the computer
+            commands.entity(boards[1])
+                .remove::<KeyControlled>()
+                .insert(AiControlled::default());
+        }
+        commands.insert_resource(rng);
+        states.set(AppState::InGame, GameState::Starting);
+    }
+}
+
+fn start_specific_level(
+    mut commands: Commands,
+    options: PressedOptions,
+    mut states: NextStates,
+    player_query: Query<(Entity, &Player)>,
+    selected_level: SelectedLevel,
+    mut run_rng: Option<ResMut<RunRng>>,
+    mut history: ResMut<RunHistory>,
+) {
+    for (id, interaction, option) in &options {
+        if !matches!((interaction, option), (Interaction::Pressed, MenuOption::SpecificLevel)) {
+            continue;
+        }
+        let (Some(level_config), Some(rng)) = (selected_level.config(id), run_rng.as_mut()) else { continue };
+        let Some(player_ent) = find_player(&player_query, 0) else { continue };
+        let board_ent = spawn_single_board_level_with_config(&mut commands, level_config, rng);
+        history.difficulty = level_config.augments.len() as u32;
+        history.augments.extend(selected_level.augment_names(level_config));
+        commands.entity(board_ent).insert(BoardPlayer(player_ent));
+        for augment in history.purchased.iter().map(|saved| saved.load()) {
+            commands.spawn_empty().add(augment).insert(InBoard(board_ent));
+        }
+        states.set(AppState::InGame, GameState::Starting);
+    }
+}
+
+// Options that only change screens, and the hover colors of every option
 pub(crate) fn handle_interactions(
     mut commands: Commands,
-    mut interaction_query: Query<(Entity, &Interaction, &MenuOption, &mut BackgroundColor, &Children), (Changed<Interaction>, With<Button>)>,
+    mut interaction_query: Query<(&Interaction, &MenuOption, &mut BackgroundColor, &Children), MenuButtonFilter>,
     mut text_query: Query<&mut Text>,
-    mut app_state: ResMut<NextState<AppState>>,
-    mut game_state: ResMut<NextState<GameState>>,
-    player_query: Query<Entity, With<Player>>,
-    level_config_query: Query<&LevelConfig>,
-    curr_game_state: Res<State<GameState>>,
-    selected_level_config: Query<&SelectedLevelConfig>,
+    mut states: NextStates,
     focused_windows: Query<(Entity, &Window)>,
 ){
-    for (id, interaction, option, mut background_color, children) in &mut interaction_query {
+    for (interaction, option, mut background_color, children) in &mut interaction_query {
         match (interaction, option) {
-            (Interaction::Pressed, MenuOption::Play) => {
-                match curr_game_state.get() {
-                    GameState::Finished | GameState::NotStarted => {
-                        let player_ent = player_query.single();
-                        let board_ent = spawn_single_board_level(&mut commands);
-                        commands.entity(board_ent).insert(BoardPlayer(player_ent));
-                        game_state.set(GameState::Starting);
-                    },
-                    GameState::Paused => game_state.set(GameState::Active),
-                    _ => {},
-                }
-                app_state.set(AppState::InGame);
-            },
-            (Interaction::Pressed, MenuOption::SpecificLevel) => {
-                // TODO the level config is not on the button entity because of the heirarchy
-                if let Ok(level_config_id) = selected_level_config.get(id) {
-                    if let Ok(level_config) = level_config_query.get(level_config_id.0){
-                        let player_ent = player_query.single();
-                        let board_ent = spawn_single_board_level_with_config(&mut commands, level_config);
-                        commands.entity(board_ent).insert(BoardPlayer(player_ent));
-                        game_state.set(GameState::Starting);
-                        app_state.set(AppState::InGame);
-                    }
-                }
+            (Interaction::Pressed, MenuOption::Controls) => {
+                states.app.set(AppState::ControlsMenu);
+            },
+            (Interaction::Pressed, MenuOption::HighScores) => {
+                states.app.set(AppState::HighScoreMenu);
+            },
+            (Interaction::Pressed, MenuOption::Suspend) => {
+                // The run is saved on the way out of the paused level, which then goes back to the main menu
+                states.game.set(GameState::NotStarted);
+            },
+            (Interaction::Pressed, MenuOption::Back) => {
+                states.app.set(AppState::MainMenu);
             },
             (Interaction::Pressed, MenuOption::Exit) => {
                 let mut text = text_query.get_mut(children[0]).unwrap();
@@ -140,20 +307,32 @@ pub(crate) fn handle_interactions(
                     commands.entity(window).despawn();
                 }
             },
-            (Interaction::Hovered, _) => {
-                let mut text = text_query.get_mut(children[0]).unwrap();
-                *background_color = Color::BLACK.into();
-                text.sections[0].style.color = Color::YELLOW.into();
-            },
-            (Interaction::None, _) => {
+            (Interaction::Pressed, _) => {},
+            (Interaction::Hovered | Interaction::None, _) => {
                 let mut text = text_query.get_mut(children[0]).unwrap();
                 *background_color = Color::BLACK.into();
-                text.sections[0].style.color = Color::WHITE.into();
+                text.sections[0].style.color = hover_color(interaction);
             },
         }
     }
 }
 
+pub(crate) fn text_style(font_size: f32, color: Color) -> TextStyle {
+    TextStyle {
+        font_size,
+        color,
+        ..default()
+    }
+}
+
+// Text on a button turns yellow while the pointer is over it
+pub(crate) fn hover_color(interaction: &Interaction) -> Color {
+    match interaction {
+        Interaction::Hovered => Color::YELLOW,
+        _ => Color::WHITE,
+    }
+}
+
 pub(crate) fn despawn(
     mut commands: Commands,
     menu: Res<MenuData>,
@@ -161,7 +340,7 @@ pub(crate) fn despawn(
     commands.entity(menu.root_entity).despawn_recursive();
 }
 
-fn add_text_button_bundle(world: &mut World, id: Entity, text: &str) {
+pub(crate) fn add_text_button_bundle(world: &mut World, id: Entity, text: &str) {
     world.entity_mut(id)
         .insert(
             ButtonBundle {
@@ -188,7 +367,7 @@ fn add_text_button_bundle(world: &mut World, id: Entity, text: &str) {
         });
 }
 
-struct MenuOptionUI;
+pub(crate) struct MenuOptionUI;
 
 impl EntityCommand for MenuOptionUI {
     fn apply(self, id: Entity, world: &mut World) {
@@ -196,9 +375,30 @@ impl EntityCommand for MenuOptionUI {
             Some(MenuOption::SpecificLevel) => {
                 add_level_button_bundle_with_icons(world, id);
             },
+            Some(MenuOption::Continue) => {
+                add_text_button_bundle(world, id, "Continue");
+            },
             Some(MenuOption::Play) => {
                 add_text_button_bundle(world, id, "Play");
             },
+            Some(MenuOption::Versus) => {
+                add_text_button_bundle(world, id, "Versus");
+            },
+            Some(MenuOption::VersusComputer) => {
+                add_text_button_bundle(world, id, "Versus Computer");
+            },
+            Some(MenuOption::Controls) => {
+                add_text_button_bundle(world, id, "Controls");
+            },
+            Some(MenuOption::HighScores) => {
+                add_text_button_bundle(world, id, "High Scores");
+            },
+            Some(MenuOption::Suspend) => {
+                add_text_button_bundle(world, id, "Save & Quit");
+            },
+            Some(MenuOption::Back) => {
+                add_text_button_bundle(world, id, "Back");
+            },
             Some(MenuOption::Exit) => {
                 add_text_button_bundle(world, id, "Exit");
             },
@@ -11,6 +11,7 @@ impl Plugin for PauseMenuPlugin {
             .add_systems(Update, handle_interactions.run_if(in_state(AppState::PauseMenu)))
             .add_systems(OnEnter(AppState::PauseMenu), spawn)
             .add_systems(OnExit(AppState::PauseMenu),  despawn)
+            .add_systems(OnTransition { from: GameState::Paused, to: GameState::NotStarted }, suspend_run)
         ;
     }
 }
@@ -18,8 +19,18 @@ impl Plugin for PauseMenuPlugin {
 fn setup(
     mut commands: Commands,
     mut state: ResMut<NextState<AppState>>,
+    level: Res<Level>,
+    simulations: Query<&BoardSimulation>,
 ) {
     commands.spawn(MenuTitle::Custom("Paused".to_string()));
     commands.spawn(MenuOption::Play);
+    // Only a recorded board played alone can be picked back up
+    let suspendable = match level.board_configs[..] {
+        [board] => simulations.get(board).is_ok_and(|simulation| simulation.simulation.recording().is_some()),
+        _ => false,
+    };
+    if suspendable {
+        commands.spawn(MenuOption::Suspend);
+    }
     state.set(AppState::PauseMenu);
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+use super::*;
+
+pub(crate) struct RunMapMenuPlugin;
+
+impl Plugin for RunMapMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::RunMap), (save_run, spawn_run_map).chain())
+            .add_systems(Update, (
+                handle_interactions,
+                handle_node_interactions,
+                visit_level_node,
+                add_icons,
+            ).run_if(in_state(AppState::RunMap)))
+            .add_systems(OnExit(AppState::RunMap), despawn)
+        ;
+    }
+}
+
+// A node without a level, moves along the map when pressed
+#[derive(Component)]
+struct NodeButton(MapPosition);
+
+fn node_color(map: &RunMap, position: MapPosition, choices: &[MapPosition]) -> Color {
+    match map.current {
+        Some(current) if current == position => Color::YELLOW,
+        _ if choices.contains(&position) => Color::GREEN,
+        Some(current) if position.depth <= current.depth => Color::DARK_GRAY,
+        _ => Color::WHITE,
+    }
+}
+
+fn spawn_map_screen(
+    commands: &mut Commands,
+    map: &RunMap,
+    run: &mut CurrentRun,
+) {
+    let (score, seed) = (run.score(), **run.seed);
+    let choices = map.choices();
+    let (mut options_id, mut footer_id) = (None, None);
+    let root_entity = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()},
+            background_color: Color::BLACK.into(),
+            ..default()})
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section("Choose a Path", text_style(80.0, Color::WHITE)));
+            builder.spawn(TextBundle::from_section(
+                format!("Levels Cleared: {}  Score: {}  Coins: {}", run.history.levels_cleared, score, run.history.coins(score)),
+                text_style(28.0, Color::WHITE),
+            ));
+
+            // The map from the boss at the top down to the first row
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::all(Val::Px(16.0)),
+                    ..default()
+                },
+                ..default()
+            }).with_children(|builder| {
+                for (depth, row) in map.rows.iter().enumerate().rev() {
+                    builder.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        },
+                        ..default()
+                    }).with_children(|builder| {
+                        for (column, node) in row.iter().enumerate() {
+                            let color = node_color(map, MapPosition { depth, column }, &choices);
+                            builder.spawn(TextBundle::from_section(node.kind.name(), text_style(24.0, color))
+                                .with_style(Style {
+                                    margin: UiRect::horizontal(Val::Px(12.0)),
+                                    ..default()
+                                }));
+                        }
+                    });
+                }
+            });
+
+            options_id = builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            })
+            .id().into();
+
+            footer_id = builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .id().into();
+        })
+        .id();
+
+    // A card for each node that can be picked next
+    for position in choices {
+        if let Some(level_config) = map.level_config(position, commands, &mut run.rng) {
+            commands.spawn((MenuOption::SpecificLevel, level_config, position))
+                .add(MenuOptionUI)
+                .set_parent(options_id.unwrap());
+            continue;
+        }
+        let Some(node) = map.node(position) else { continue };
+        commands.spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(320.0),
+                    margin: UiRect::all(Val::Px(8.0)),
+                    border: UiRect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                border_color: Color::GRAY.into(),
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            NodeButton(position),
+        )).with_children(|builder| {
+            builder.spawn(TextBundle::from_section(node.kind.name(), text_style(40.0, Color::WHITE)));
+        })
+        .set_parent(options_id.unwrap());
+    }
+
+    commands.spawn(MenuOption::Back)
+        .add(MenuOptionUI)
+        .set_parent(footer_id.unwrap());
+    let seed_id = commands.spawn(TextBundle::from_section(format!("Seed: {}", seed), text_style(28.0, Color::WHITE))).id();
+    commands.entity(footer_id.unwrap()).add_child(seed_id);
+    commands.insert_resource(MenuData { root_entity });
+}
+
+fn spawn_run_map(
+    mut commands: Commands,
+    map: Res<RunMap>,
+    mut run: CurrentRun,
+) {
+    spawn_map_screen(&mut commands, &map, &mut run);
+}
+
+// Resting saves the run there and shows the next choices, a shop is opened
+fn handle_node_interactions(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &NodeButton, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut map: ResMut<RunMap>,
+    menu: Res<MenuData>,
+    mut run: CurrentRun,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, button, children) in buttons.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed => {
+                map.visit(button.0);
+                if map.node(button.0).is_some_and(|node| node.kind == NodeKind::Shop) {
+                    // Saved on the way back, so quitting in the shop doesn't use it up
+                    app_state.set(AppState::ShopMenu);
+                    return;
+                }
+                run.save(Some(&map)).write();
+                commands.entity(menu.root_entity).despawn_recursive();
+                spawn_map_screen(&mut commands, &map, &mut run);
+                // The screen has been rebuilt without the other buttons
+                return;
+            },
+            Interaction::Hovered | Interaction::None => text.sections[0].style.color = hover_color(interaction),
+        }
+    }
+}
+
+// The level itself is started by the menu, this moves the map along to its node
+fn visit_level_node(
+    buttons: Query<(&Interaction, &SelectedLevelConfig), Changed<Interaction>>,
+    positions: Query<&MapPosition>,
+    mut map: ResMut<RunMap>,
+) {
+    for (interaction, config_id) in buttons.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Ok(position) = positions.get(config_id.0) {
+            map.visit(*position);
+        }
+    }
+}
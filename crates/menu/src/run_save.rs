@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use bevy::ecs::system::SystemParam;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::*;
+
+pub const RUN_SAVE_FILE: &str = "run.ron";
+
+pub(crate) fn run_save_path() -> PathBuf {
+    data_path(RUN_SAVE_FILE)
+}
+
+// The level the run was suspended in, with the augments on its board
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct LevelSnapshot {
+    pub board: BoardSnapshot,
+    pub augments: Vec<SavedAugment>,
+}
+
+// Everything needed to pick a run back up at the run map it was saved from, or the level it was suspended in
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RunSave {
+    pub seed: u64,
+    // Seed the run's stream was restarted from when it was saved
+    pub rng_state: u64,
+    pub score: usize,
+    pub levels_cleared: u32,
+    pub difficulty: u32,
+    pub augments: Vec<String>,
+    #[serde(default)]
+    pub spent: u32,
+    #[serde(default)]
+    pub purchased: Vec<SavedAugment>,
+    #[serde(default)]
+    pub map: Option<RunMap>,
+    #[serde(default)]
+    pub level: Option<LevelSnapshot>,
+}
+
+impl RunSave {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        load_ron(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        save_ron(path, self)
+    }
+
+    // The run between levels, the stream is restarted so the choices made from it after loading are the same
+    pub fn between_levels(
+        players: &Query<(&Player, Option<&GlobalScore>)>,
+        history: &RunHistory,
+        seed: &RunSeed,
+        rng: &mut RunRng,
+        map: Option<&RunMap>,
+    ) -> Self {
+        Self {
+            seed: **seed,
+            rng_state: rng.reseed(),
+            score: player_score(players),
+            levels_cleared: history.levels_cleared,
+            difficulty: history.difficulty,
+            augments: history.augments.clone(),
+            spent: history.spent,
+            purchased: history.purchased.clone(),
+            map: map.cloned(),
+            level: None,
+        }
+    }
+
+    pub(crate) fn write(&self) {
+        let path = run_save_path();
+        if let Err(err) = self.save(&path) {
+            warn!("Failed to save the run to {}: {}", path.display(), err);
+        }
+    }
+}
+
+// The run in progress, as much of it as is kept between levels
+#[derive(SystemParam)]
+pub(crate) struct CurrentRun<'w, 's> {
+    pub players: Query<'w, 's, (&'static Player, Option<&'static GlobalScore>)>,
+    pub history: ResMut<'w, RunHistory>,
+    pub seed: Res<'w, RunSeed>,
+    pub rng: ResMut<'w, RunRng>,
+}
+
+impl CurrentRun<'_, '_> {
+    pub fn score(&self) -> usize {
+        player_score(&self.players)
+    }
+
+    pub fn save(&mut self, map: Option<&RunMap>) -> RunSave {
+        RunSave::between_levels(&self.players, &self.history, &self.seed, &mut self.rng, map)
+    }
+}
+
+// The run's score so far, carried by the first player
+pub(crate) fn player_score(players: &Query<(&Player, Option<&GlobalScore>)>) -> usize {
+    players.iter()
+        .find(|(player, _)| player.0 == 0)
+        .and_then(|(_, score)| score)
+        .map_or(0, |score| score.0)
+}
+
+// The run is over, so there is nothing to continue
+pub(crate) fn remove_run_save() {
+    let path = run_save_path();
+    if path.exists() {
+        if let Err(err) = fs::remove_file(&path) {
+            warn!("Failed to remove {}: {}", path.display(), err);
+        }
+    }
+}
+
+// Saves whenever the run map is shown, before the levels on it are made
+pub(crate) fn save_run(
+    mut run: CurrentRun,
+    map: Option<Res<RunMap>>,
+) {
+    run.save(map.as_deref()).write();
+}
+
+// Puts the saved run back and spawns the level it was suspended in, the run map is shown otherwise
+pub(crate) fn continue_run(
+    commands: &mut Commands,
+    run: &RunSave,
+    player: Entity,
+) {
+    let mut rng = RunRng::resume(run.rng_state);
+    commands.insert_resource(RunSeed(run.seed));
+    commands.insert_resource(RunHistory {
+        levels_cleared: run.levels_cleared,
+        difficulty: run.difficulty,
+        augments: run.augments.clone(),
+        spent: run.spent,
+        purchased: run.purchased.clone(),
+    });
+    commands.entity(player).insert(GlobalScore(run.score));
+    match (&run.level, &run.map) {
+        (Some(level), map) => {
+            let board_ent = spawn_restored_level(commands, level.board.clone());
+            commands.entity(board_ent).insert(BoardPlayer(player));
+            for augment in level.augments.iter().map(|saved| saved.load()) {
+                commands.spawn_empty().add(augment).insert(InBoard(board_ent));
+            }
+            if let Some(map) = map {
+                commands.insert_resource(map.clone());
+            }
+        },
+        (None, Some(map)) => commands.insert_resource(map.clone()),
+        // Saved before runs had a map
+        (None, None) => commands.insert_resource(RunMap::generate(&mut rng)),
+    }
+    commands.insert_resource(rng);
+}
+
+type SuspendedBoard<'a> = (
+    &'a BoardSimulation,
+    &'a BoardConfig,
+    Option<&'a DropTimer>,
+    Option<&'a SpeedCurve>,
+    Option<&'a Score>,
+);
+
+type PieceTraitsQuery<'a> = (Option<&'a Stacked>, Option<&'a RemoveStack>, Option<&'a Explosive>);
+
+// Quitting from the pause menu saves the run with the level as it is, to be picked back up with Continue
+pub(crate) fn suspend_run(
+    level: Res<Level>,
+    boards: Query<SuspendedBoard>,
+    pieces: Query<PieceTraitsQuery>,
+    augments: Query<(&SavedAugment, &InBoard)>,
+    mut run: CurrentRun,
+    map: Option<Res<RunMap>>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    // Saved before the main menu is shown so it can offer to continue
+    app_state.set(AppState::MainMenu);
+    let [board_id] = level.board_configs[..] else { return };
+    let Ok((simulation, config, drop_timer, speed, board_score)) = boards.get(board_id) else { return };
+    let traits = |entity| pieces.get(entity).ok().map(|(stacked, remove_stack, explosive)| PieceTraits {
+        stacks: stacked.map_or(0, |stacked| stacked.0),
+        remove_stacks: remove_stack.map_or(0, |remove_stack| remove_stack.0),
+        explosive: explosive.map_or(AreaOfEffect::default(), |explosive| explosive.0),
+    });
+    let Some(snapshot) = BoardSnapshot::capture(
+        simulation,
+        traits,
+        config.drop_period,
+        drop_timer.map_or(0.0, |timer| timer.elapsed_secs()),
+        speed.cloned().unwrap_or_default(),
+        board_score.map_or(0, |score| score.0),
+    ) else { return };
+    let save = RunSave {
+        level: Some(LevelSnapshot {
+            board: snapshot,
+            augments: augments.iter()
+                .filter(|(_, in_board)| ***in_board == board_id)
+                .map(|(saved, _)| saved.clone())
+                .collect(),
+        }),
+        ..run.save(map.as_deref())
+    };
+    save.write();
+}
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use super::*;
+
+// Augments on sale each time the shop is visited
+const SHOP_SIZE: usize = 3;
+
+pub(crate) struct ShopMenuPlugin;
+
+impl Plugin for ShopMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::ShopMenu), spawn_shop)
+            .add_systems(Update, (
+                handle_offer_interactions,
+                handle_leave_interactions,
+            ).run_if(in_state(AppState::ShopMenu)))
+            .add_systems(OnExit(AppState::ShopMenu), despawn)
+        ;
+    }
+}
+
+// An augment for sale, bought when pressed if there are enough coins
+#[derive(Component)]
+struct ShopOffer {
+    augment: SavedAugment,
+    price: u32,
+}
+
+// Goes back to the run map
+#[derive(Component)]
+struct LeaveButton;
+
+#[derive(Component)]
+struct CoinsText;
+
+fn describe(augment: &SavedAugment) -> String {
+    match augment {
+        SavedAugment::Frequency { amount } => format!("{} more viruses", amount),
+        SavedAugment::Potency { amount, filter } => format!("+{} clears on {}", amount, filter.name()),
+        SavedAugment::Resilience { amount, filter } => format!("+{} stacks on {}", amount, filter.name()),
+        SavedAugment::Urgency { amount } => format!("{}s faster drops", amount),
+        SavedAugment::Volatility { area: AreaOfEffect::Radius(radius), filter } => format!("Radius {} blasts from {}", radius, filter.name()),
+        SavedAugment::Volatility { filter, .. } => format!("Blasts from {}", filter.name()),
+    }
+}
+
+fn coins_label(coins: u32) -> String {
+    format!("Coins: {}", coins)
+}
+
+fn spawn_shop(
+    mut commands: Commands,
+    mut rng: ResMut<RunRng>,
+    history: Res<RunHistory>,
+    players: Query<(&Player, Option<&GlobalScore>)>,
+) {
+    let coins = history.coins(player_score(&players));
+    let root_entity = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()},
+            background_color: Color::BLACK.into(),
+            ..default()})
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_section("Shop", text_style(80.0, Color::WHITE)));
+            builder.spawn((
+                TextBundle::from_section(coins_label(coins), text_style(28.0, Color::WHITE)),
+                CoinsText,
+            ));
+
+            // A card for each augment on sale
+            builder.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    margin: UiRect::all(Val::Px(16.0)),
+                    ..default()
+                },
+                ..default()
+            }).with_children(|builder| {
+                for _ in 0..SHOP_SIZE {
+                    let augment = random_helpful_augment(&mut **rng);
+                    let price = augment.cost();
+                    let name = augment.name();
+                    let saved = augment.save();
+                    builder.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(320.0),
+                                margin: UiRect::all(Val::Px(8.0)),
+                                border: UiRect::all(Val::Px(2.0)),
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            border_color: Color::GRAY.into(),
+                            background_color: Color::BLACK.into(),
+                            ..default()
+                        },
+                        ShopOffer { augment: saved.clone(), price },
+                    )).with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(format!("{} - {}", name, price), text_style(40.0, Color::WHITE)));
+                        builder.spawn(TextBundle::from_section(describe(&saved), text_style(24.0, Color::GRAY)));
+                    });
+                }
+            });
+
+            builder.spawn((
+                ButtonBundle {
+                    style: Style {
+                        padding: UiRect::vertical(Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::BLACK.into(),
+                    ..default()
+                },
+                LeaveButton,
+            )).with_children(|builder| {
+                builder.spawn(TextBundle::from_section("Leave", text_style(40.0, Color::WHITE)));
+            });
+        })
+        .id();
+    commands.insert_resource(MenuData { root_entity });
+}
+
+fn handle_offer_interactions(
+    mut commands: Commands,
+    offers: Query<(Entity, &Interaction, &ShopOffer, &Children), Changed<Interaction>>,
+    mut texts: Query<&mut Text, Without<CoinsText>>,
+    mut coins_text: Query<&mut Text, With<CoinsText>>,
+    mut history: ResMut<RunHistory>,
+    players: Query<(&Player, Option<&GlobalScore>)>,
+) {
+    for (id, interaction, offer, children) in offers.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed => {
+                let coins = history.coins(player_score(&players));
+                if coins < offer.price {
+                    text.sections[0].style.color = Color::RED;
+                    continue;
+                }
+                history.spent += offer.price;
+                history.purchased.push(offer.augment.clone());
+                for mut coins_text in coins_text.iter_mut() {
+                    coins_text.sections[0].value = coins_label(coins - offer.price);
+                }
+                commands.entity(id).despawn_recursive();
+            },
+            Interaction::Hovered | Interaction::None => text.sections[0].style.color = hover_color(interaction),
+        }
+    }
+}
+
+fn handle_leave_interactions(
+    buttons: Query<(&Interaction, &Children, &LeaveButton), Changed<Interaction>>,
+    mut texts: Query<&mut Text>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, children, _) in buttons.iter() {
+        let Ok(mut text) = texts.get_mut(children[0]) else { continue };
+        match interaction {
+            Interaction::Pressed => app_state.set(AppState::RunMap),
+            Interaction::Hovered | Interaction::None => text.sections[0].style.color = hover_color(interaction),
+        }
+    }
+}
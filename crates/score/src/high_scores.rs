@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+use pills_core::*;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const HIGH_SCORES_FILE: &str = "high_scores.ron";
+
+// Entries kept for each mode
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameMode {
+    Single,
+    Versus,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Single, GameMode::Versus];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Single => "Single",
+            GameMode::Versus => "Versus",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: usize,
+    pub mode: GameMode,
+    // Augments on the level the run ended on
+    pub difficulty: u32,
+    // Seconds since the unix epoch
+    pub date: u64,
+    pub levels_cleared: u32,
+    pub augments: Vec<String>,
+}
+
+impl HighScore {
+    pub fn new(mode: GameMode, score: usize) -> Self {
+        let date = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        Self { score, mode, difficulty: 0, date, levels_cleared: 0, augments: vec![] }
+    }
+
+    // The day it was set as year-month-day, in UTC
+    pub fn day(&self) -> String {
+        // Days to a civil date, from Howard Hinnant's date algorithms
+        let days = (self.date / 86400) as i64 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// Where the table is kept between sessions
+pub trait HighScoreStorage: Send + Sync {
+    fn load(&self) -> Result<Vec<HighScore>, Box<dyn Error>>;
+    fn save(&mut self, scores: &[HighScore]) -> Result<(), Box<dyn Error>>;
+}
+
+// A ron file, by default in the user's data directory
+pub struct FileStorage {
+    path: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn in_data_dir() -> Self {
+        Self::new(data_path(HIGH_SCORES_FILE))
+    }
+}
+
+impl HighScoreStorage for FileStorage {
+    fn load(&self) -> Result<Vec<HighScore>, Box<dyn Error>> {
+        if !self.path.exists() {
+            return Ok(vec![]);
+        }
+        load_ron(&self.path)
+    }
+
+    fn save(&mut self, scores: &[HighScore]) -> Result<(), Box<dyn Error>> {
+        save_ron(&self.path, scores)
+    }
+}
+
+#[derive(Default)]
+pub struct MemoryStorage(pub Vec<HighScore>);
+
+impl HighScoreStorage for MemoryStorage {
+    fn load(&self) -> Result<Vec<HighScore>, Box<dyn Error>> {
+        Ok(self.0.clone())
+    }
+
+    fn save(&mut self, scores: &[HighScore]) -> Result<(), Box<dyn Error>> {
+        self.0 = scores.to_vec();
+        Ok(())
+    }
+}
+
+// The best scores for each mode, highest first
+#[derive(Resource)]
+pub struct HighScores {
+    scores: Vec<HighScore>,
+    storage: Box<dyn HighScoreStorage>,
+}
+
+impl HighScores {
+    pub fn new(storage: Box<dyn HighScoreStorage>) -> Self {
+        let scores = storage.load().unwrap_or_else(|err| {
+            warn!("Failed to load the high scores: {}", err);
+            vec![]
+        });
+        Self { scores, storage }
+    }
+
+    pub fn scores(&self, mode: GameMode) -> impl Iterator<Item = &HighScore> {
+        self.scores.iter().filter(move |entry| entry.mode == mode)
+    }
+
+    // Adds the score if it makes the table for its mode and returns its place, counting from 0
+    pub fn record(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.scores(entry.mode).take_while(|other| other.score >= entry.score).count();
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        let index = self.scores.iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.scores.len());
+        let mode = entry.mode;
+        self.scores.insert(index, entry);
+        let mut kept = 0;
+        self.scores.retain(|other| {
+            if other.mode != mode {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_HIGH_SCORES
+        });
+        if let Err(err) = self.storage.save(&self.scores) {
+            warn!("Failed to save the high scores: {}", err);
+        }
+        Some(rank)
+    }
+}
+
+pub(crate) fn load_high_scores(
+    mut commands: Commands,
+) {
+    commands.insert_resource(HighScores::new(Box::new(FileStorage::in_data_dir())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(mode: GameMode, score: usize) -> HighScore {
+        HighScore { date: 0, ..HighScore::new(mode, score) }
+    }
+
+    #[test]
+    fn test_record_keeps_the_best_scores_per_mode() {
+        let mut high_scores = HighScores::new(Box::new(MemoryStorage::default()));
+        for score in 0..MAX_HIGH_SCORES {
+            assert!(high_scores.record(entry(GameMode::Single, score * 10)).is_some());
+        }
+        // Ties with the last place do not push it out
+        assert_eq!(high_scores.record(entry(GameMode::Single, 0)), None);
+        assert_eq!(high_scores.record(entry(GameMode::Single, 45)), Some(5));
+        assert_eq!(high_scores.record(entry(GameMode::Versus, 1)), Some(0));
+
+        let singles: Vec<usize> = high_scores.scores(GameMode::Single).map(|entry| entry.score).collect();
+        assert_eq!(singles, vec![90, 80, 70, 60, 50, 45, 40, 30, 20, 10]);
+        assert_eq!(high_scores.scores(GameMode::Versus).count(), 1);
+    }
+
+    #[test]
+    fn test_scores_are_saved_to_storage() {
+        let mut high_scores = HighScores::new(Box::new(MemoryStorage(vec![entry(GameMode::Single, 7)])));
+        high_scores.record(entry(GameMode::Single, 12));
+        let saved = high_scores.storage.load().unwrap();
+        assert_eq!(saved.iter().map(|entry| entry.score).collect::<Vec<_>>(), vec![12, 7]);
+    }
+
+    #[test]
+    fn test_day_is_the_utc_date() {
+        let high_score = HighScore { date: 1_700_000_000, ..entry(GameMode::Single, 0) };
+        assert_eq!(high_score.day(), "2023-11-14");
+    }
+}
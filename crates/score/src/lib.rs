@@ -2,11 +2,17 @@ use bevy::prelude::*;
 use bevy::sprite::Anchor;
 use pills_core::*;
 use pills_level::*;
+
+pub use high_scores::*;
+
+mod high_scores;
+
 pub struct ScorePlugin;
 
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
         app
+            .add_systems(Startup, load_high_scores)
             .add_systems(
                 OnTransition { from: GameState::Starting, to: GameState::Active}, 
                 add_score_tracking)
@@ -45,13 +51,28 @@ pub struct ScoreChange {
     pub score_entity: Entity, 
     pub source_entity: Entity,
     pub position: Option<(u8, u8)>,
-    pub amount: i32
+    pub amount: i32,
+    // The amount is multiplied by this, and it is shown next to it when more than 1
+    pub multiplier: i32,
+}
+
+// One clear of a cascade, used to work out the multiplier for the viruses it removed
+#[derive(Clone, Copy, Debug)]
+pub struct ClearInfo {
+    // Clears since the pill locked, starting at 1
+    pub chain: usize,
+    // Matches cleared at the same time
+    pub groups: usize,
+    pub viruses: usize,
+    // How many steps the board's speed curve has taken
+    pub speed: usize,
 }
 
 #[derive(Component)]
 pub struct ScorePolicy {
     virus_removed: fn(&Virus) -> i32,
     pill_added: fn(&Pill) -> i32,
+    clear_multiplier: fn(&ClearInfo) -> i32,
 }
 
 impl Default for ScorePolicy {
@@ -59,6 +80,10 @@ impl Default for ScorePolicy {
         Self {
             virus_removed: |_| 1,
             pill_added: |_| 0,
+            clear_multiplier: |clear| {
+                let combo = clear.chain * clear.groups.max(1) + clear.viruses.saturating_sub(1);
+                (combo * (1 + clear.speed / 5)) as i32
+            },
         }
     }
 }
@@ -66,15 +91,24 @@ impl Default for ScorePolicy {
 #[derive(Component)]
 pub struct ScoreBoard(pub Entity);
 
+type UnscoredBoard<'a> = (
+    Entity,
+    Option<&'a ScorePolicy>,
+    Option<&'a BoardInfoContainer>,
+    Option<&'a BoardPlayer>,
+    Option<&'a RestoreBoard>,
+);
+
 fn add_score_tracking(
     mut commands: Commands,
-    query: Query<(Entity, Option<&ScorePolicy>, Option<&BoardInfoContainer>, Option<&BoardPlayer>), (With<GameBoard>, Without<Score>)>,
+    query: Query<UnscoredBoard, (With<GameBoard>, Without<Score>)>,
     player_score: Query<&GlobalScore>,
 ) {
-    for (entity, maybe_policy, maybe_container, maybe_player) in query.iter() {
+    for (entity, maybe_policy, maybe_container, maybe_player, maybe_restore) in query.iter() {
         info!("Adding score tracking to {:?}", entity);
+        let score = maybe_restore.map_or(0, |restore| restore.0.score);
         commands.entity(entity)
-            .insert(Score(0))
+            .insert(Score(score))
         ;
         if maybe_policy.is_none() {
             commands.entity(entity)
@@ -85,7 +119,7 @@ fn add_score_tracking(
             let score_board_ent = commands.spawn((
                 Text2dBundle {
                     text: Text::from_section(
-                        "Score: 0".to_string(),
+                        format!("Score: {}", score),
                         TextStyle {font_size: 32.0, color: Color::WHITE, ..default()}
                     ),
                     text_anchor: Anchor::TopLeft,
@@ -145,12 +179,26 @@ fn update_score_board(
     }
 }
 
+fn virus_score_change(policy: &ScorePolicy, removed: &VirusRemoved, multiplier: i32) -> ScoreChange {
+    let f = policy.virus_removed;
+    ScoreChange {
+        score_entity: removed.board,
+        source_entity: removed.piece,
+        position: Some((removed.row, removed.col)),
+        amount: f(&removed.virus),
+        multiplier,
+    }
+}
+
 fn insert_score_changes(
     mut commands: Commands,
     mut events: EventReader<BoardEvent>,
     policies: Query<&ScorePolicy>,
+    speeds: Query<&SpeedCurve>,
 ) {
-    for event in events.iter() {
+    // Viruses are removed just before the clear they belong to, so they wait for it to know their multiplier
+    let mut removed_viruses: Vec<&VirusRemoved> = vec![];
+    for event in events.read() {
         match event {
             BoardEvent::PillAdded(added) => {
                 if let Ok(policy) = policies.get(added.board) {
@@ -159,24 +207,46 @@ fn insert_score_changes(
                         score_entity: added.board, 
                         source_entity: added.piece,
                         position: None,
-                        amount:f(&added.pill)
+                        amount:f(&added.pill),
+                        multiplier: 1,
                     });
                 }
             },
             BoardEvent::VirusRemoved(removed) => {
-                if let Ok(policy) = policies.get(removed.board) {
-                    let f = policy.virus_removed;
-                    commands.spawn(ScoreChange{
-                        score_entity: removed.board, 
-                        source_entity: removed.piece,
-                        position: Some((removed.row, removed.col)),
-                        amount:f(&removed.virus)
-                    });
+                removed_viruses.push(removed);
+            },
+            BoardEvent::CellsCleared(cleared) => {
+                let (viruses, others) = removed_viruses.into_iter().partition(|removed| removed.board == cleared.board);
+                removed_viruses = others;
+                let Ok(policy) = policies.get(cleared.board) else { continue };
+                let clear = ClearInfo {
+                    chain: cleared.chain,
+                    groups: cleared.groups,
+                    viruses: viruses.len(),
+                    speed: speeds.get(cleared.board).map_or(0, |speed| speed.level()),
+                };
+                let multiplier = (policy.clear_multiplier)(&clear).max(1);
+                for removed in viruses {
+                    commands.spawn(virus_score_change(policy, removed, multiplier));
                 }
             },
             _ => {},
         }
     }
+    for removed in removed_viruses {
+        if let Ok(policy) = policies.get(removed.board) {
+            commands.spawn(virus_score_change(policy, removed, 1));
+        }
+    }
+}
+
+// A score clamped at zero shows what was actually taken off instead of the multiplied amount
+fn score_change_text(change: &ScoreChange, actual_amount: i32) -> String {
+    if change.multiplier > 1 && actual_amount == change.amount * change.multiplier {
+        format!("{} x{}", change.amount, change.multiplier)
+    } else {
+        format!("{}", actual_amount)
+    }
 }
 
 fn apply_score_changes(
@@ -187,18 +257,19 @@ fn apply_score_changes(
     for (entity, change) in score_changes.iter() {
         if let Ok(mut score) = scores.get_mut(change.score_entity) {
             let mut text_color = Color::WHITE;
-            let mut actual_amount = change.amount;
-            if change.amount < 0 {
-                if change.amount.abs() as usize > score.0 {
+            let amount = change.amount * change.multiplier;
+            let mut actual_amount = amount;
+            if amount < 0 {
+                if amount.unsigned_abs() as usize > score.0 {
                     actual_amount = -(score.0 as i32);
                     score.0 = 0;
                 } else {
-                    score.0 -= change.amount.abs() as usize;
+                    score.0 -= amount.unsigned_abs() as usize;
                 }
                 text_color = Color::RED;
             } else {
-                score.0 += change.amount as usize;
-                if change.amount > 0 {
+                score.0 += amount as usize;
+                if amount > 0 {
                     text_color = Color::GREEN;
                 }
             }
@@ -209,7 +280,7 @@ fn apply_score_changes(
                 commands.spawn((
                     Text2dBundle {
                         text: Text::from_section(
-                            format!("{}", actual_amount).to_string(),
+                            score_change_text(change, actual_amount),
                             TextStyle {font_size: 32.0, color: text_color, ..default()}
                         ),
                         ..default()
@@ -223,4 +294,54 @@ fn apply_score_changes(
         }
         commands.entity(entity).remove::<ScoreChange>();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn multiplier(chain: usize, groups: usize, viruses: usize, speed: usize) -> i32 {
+        (ScorePolicy::default().clear_multiplier)(&ClearInfo { chain, groups, viruses, speed })
+    }
+
+    fn change(amount: i32, multiplier: i32) -> ScoreChange {
+        ScoreChange {
+            score_entity: Entity::PLACEHOLDER,
+            source_entity: Entity::PLACEHOLDER,
+            position: None,
+            amount,
+            multiplier,
+        }
+    }
+
+    #[test]
+    fn test_single_group_is_not_multiplied() {
+        assert_eq!(multiplier(1, 1, 1, 0), 1);
+        assert_eq!(multiplier(1, 1, 3, 0), 3);
+    }
+
+    #[test]
+    fn test_simultaneous_groups_multiply_the_chain() {
+        assert_eq!(multiplier(1, 2, 2, 0), 3);
+    }
+
+    #[test]
+    fn test_later_chains_score_more() {
+        assert_eq!(multiplier(2, 1, 1, 0), 2);
+        assert_eq!(multiplier(3, 2, 1, 0), 6);
+    }
+
+    #[test]
+    fn test_speed_bonus_every_five_steps() {
+        assert_eq!(multiplier(1, 1, 1, 4), 1);
+        assert_eq!(multiplier(1, 1, 1, 5), 2);
+        assert_eq!(multiplier(2, 1, 1, 10), 6);
+    }
+
+    #[test]
+    fn test_clamped_change_shows_the_amount_taken() {
+        assert_eq!(score_change_text(&change(2, 3), 6), "2 x3");
+        assert_eq!(score_change_text(&change(-2, 3), -6), "-2 x3");
+        assert_eq!(score_change_text(&change(-2, 3), -4), "-4");
+        assert_eq!(score_change_text(&change(-5, 1), -3), "-3");
+    }
+}
@@ -11,6 +11,10 @@ struct BoardCount(usize);
 #[derive(Component)]
 struct BoardBackground(usize);
 
+// Shows how far along its speed curve a board is
+#[derive(Component)]
+struct SpeedIndicator(Entity);
+
 pub(crate) struct BoardSpritesPlugin;
 
 impl Plugin for BoardSpritesPlugin {
@@ -18,6 +22,7 @@ impl Plugin for BoardSpritesPlugin {
         app
             .init_resource::<BoardCount>()
             .add_systems(OnEnter(GameState::Starting), add_board_sprites)
+            .add_systems(Update, (add_speed_indicators, update_speed_indicators))
             .add_systems(
                 PostUpdate, 
                 update_transforms
@@ -33,6 +38,8 @@ fn add_board_sprites(
     boards: Query<&BoardConfig>,
 ) {
     let mut num_boards = 0;
+    // Every board hangs off one root so the whole level is despawned together
+    let root = commands.spawn(SpatialBundle::default()).id();
     let configs = level.board_configs.clone();
     for board_entity in configs.iter() {
         if let Ok(config) = boards.get(*board_entity) {
@@ -56,6 +63,7 @@ fn add_board_sprites(
                     },
                     BoardBackground(num_boards),
                 ))
+                .set_parent(root)
                 .id()
             ;
             let info_container = commands
@@ -89,10 +97,10 @@ fn add_board_sprites(
                 ))
                 .set_parent(background_entity)
             ;
-            level.root = Some(background_entity);
             num_boards += 1;
         }
     }
+    level.root = Some(root);
     board_count.set_if_neq(BoardCount(num_boards));
 }
 
@@ -100,7 +108,7 @@ fn update_transforms(
     mut boards: Query<(&BoardBackground, &Sprite, &mut Transform), Added<BoardBackground>>,
     transforms: Query<(&GlobalTransform, &Node)>,
     content_container: Res<ContentContainer>,
-
+    board_count: Res<BoardCount>,
 ) {
     if boards.is_empty() {
         return;
@@ -111,10 +119,48 @@ fn update_transforms(
             //let mut x = -content_size.size().x/2.0;
             let mut x = 0.0;
             if let Some(size) = sprite.custom_size {
-                x += (background.0 as f32) * size.x;
+                // Spread the boards out around the middle with a cell of space between them
+                let offset = background.0 as f32 - (board_count.0 as f32 - 1.0) / 2.0;
+                x += offset * (size.x + CELL_SIZE);
                 info!("Board offset {:?}", x);
             }
             transform.translation = Vec3::new(x, 0.0, 1.0);
         }
     }
-}
\ No newline at end of file
+}
+
+fn speed_text(curve: &SpeedCurve) -> String {
+    format!("Speed {}", curve.level() + 1)
+}
+
+// Goes in the top right of the info container, above the next pill
+fn add_speed_indicators(
+    mut commands: Commands,
+    boards: Query<(Entity, &BoardInfoContainer, &SpeedCurve), Added<BoardInfoContainer>>,
+    sprites: Query<&Sprite>,
+) {
+    for (board, container, curve) in boards.iter() {
+        let Some(size) = sprites.get(container.0).ok().and_then(|sprite| sprite.custom_size) else { continue };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(speed_text(curve), TextStyle { font_size: 14.0, color: Color::WHITE, ..default() }),
+                text_anchor: Anchor::TopRight,
+                transform: Transform::from_xyz(size.x - 4.0, 0.0, 1.0),
+                ..default()
+            },
+            SpeedIndicator(board),
+        ))
+            .set_parent(container.0);
+    }
+}
+
+fn update_speed_indicators(
+    mut indicators: Query<(&SpeedIndicator, &mut Text)>,
+    curves: Query<&SpeedCurve, Changed<SpeedCurve>>,
+) {
+    for (indicator, mut text) in indicators.iter_mut() {
+        if let Ok(curve) = curves.get(indicator.0) {
+            text.sections[0].value = speed_text(curve);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use pills_core::*;
+use super::*;
+
+pub struct GhostSpritesPlugin;
+
+impl Plugin for GhostSpritesPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(
+                PostUpdate,
+                update_ghost_pieces
+                    .before(bevy::transform::TransformSystem::TransformPropagate)
+            )
+        ;
+    }
+}
+
+const GHOST_ALPHA: f32 = 0.3;
+
+// The translucent halves drawn where the board's active pill will land
+#[derive(Component)]
+struct GhostSprites([Entity; 2]);
+
+fn update_ghost_pieces(
+    mut commands: Commands,
+    boards: Query<(Entity, &GameBoard, Option<&GhostSprites>), With<GhostPiece>>,
+    pivots: Query<(Entity, &InBoard), With<PivotPiece>>,
+    mut ghosts: Query<(&mut Transform, &mut TextureAtlasSprite, &mut Visibility)>,
+    atlas_handle: Option<Res<PieceAtlasHandle>>,
+) {
+    let Some(atlas_handle) = atlas_handle else { return };
+    for (board_id, board, ghost_sprites) in boards.iter() {
+        let Some(GhostSprites(ghost_ids)) = ghost_sprites else {
+            let ghost_ids = [0, 1].map(|_| commands
+                .spawn(SpriteSheetBundle {
+                    texture_atlas: (**atlas_handle).clone(),
+                    sprite: TextureAtlasSprite { index: 5, ..default() },
+                    transform: Transform::from_scale(Vec3::new(0.5, 0.5, 1.0)),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                })
+                .set_parent(board_id)
+                .id());
+            commands.entity(board_id).insert(GhostSprites(ghost_ids));
+            continue;
+        };
+        // The board knows where the pivot is even in the middle of a rotation, its BoardPosition may not
+        let pivot = pivots.iter()
+            .find(|(_, in_board)| ***in_board == board_id)
+            .and_then(|(pivot, _)| board.cells.iter().position(|cell| cell.get() == Some(pivot)))
+            .map(|index| board.get_row_col(index));
+        let landing = pivot.and_then(|(row, col)| {
+            let (cell, Some((other, _, _))) = board.get_paired(row, col) else { return None };
+            board.landing((row, col)).ok().map(|positions| [(cell, positions[0]), (other, positions[1])])
+        });
+        for (index, ghost_id) in ghost_ids.iter().enumerate() {
+            let Ok((mut transform, mut sprite, mut visibility)) = ghosts.get_mut(*ghost_id) else { continue };
+            let Some((cell, (row, col))) = landing.map(|landing| landing[index]) else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+            // Dropping never turns the pill, so each half keeps its color and orientation
+            if let (Some(color), Some(orientation)) = (cell.color(), cell.get_orientation()) {
+                sprite.color = pill_color(color).with_a(GHOST_ALPHA);
+                transform.rotation = orientation_rotation(orientation);
+            }
+            transform.translation = cell_translation(board, row, col).extend(50.0);
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
 use board::*;
+use ghost::*;
 use pieces::*;
 
 mod board;
+mod ghost;
 mod pieces;
 
 pub const CELL_SIZE: f32 = 32.0;
@@ -14,5 +16,6 @@ impl PluginGroup for PillsSpritesPluginGroup {
         PluginGroupBuilder::start::<Self>()
             .add(BoardSpritesPlugin)
             .add(PieceSpritesPlugin)
+            .add(GhostSpritesPlugin)
     }
 }
\ No newline at end of file
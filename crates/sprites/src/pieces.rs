@@ -11,10 +11,10 @@ impl Plugin for PieceSpritesPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_systems(Startup, setup_resources)
-            .add_systems(Update, (add_pill_sprites, add_virus_sprites, add_cleared_sprites, update_stack_indicator))
+            .add_systems(Update, (add_pill_sprites, add_virus_sprites, add_cleared_sprites, update_stack_indicator, parent_preview_pills))
             .add_systems(
                 PostUpdate, 
-                update_transforms
+                (update_transforms, update_held_transforms)
                     .before(bevy::transform::TransformSystem::TransformPropagate)
             )
         ;
@@ -26,7 +26,35 @@ const YELLOW_COLOR : Color = Color::rgb(255.0/255.0, 213.0/255.0, 96.0/255.0);
 const BLUE_COLOR : Color = Color::rgb(0.0/255.0, 194.0/255.0, 215.0/255.0);
 
 #[derive(Resource, Deref, DerefMut)]
-struct PieceAtlasHandle(Handle<TextureAtlas>);
+pub(crate) struct PieceAtlasHandle(Handle<TextureAtlas>);
+
+pub(crate) fn pill_color(color: CellColor) -> Color {
+    match color {
+        CellColor::RED => RED_COLOR,
+        CellColor::YELLOW => YELLOW_COLOR,
+        CellColor::BLUE => BLUE_COLOR,
+        CellColor::ORANGE => RED_COLOR,
+        CellColor::GREEN => YELLOW_COLOR,
+        CellColor::PURPLE => BLUE_COLOR,
+    }
+}
+
+pub(crate) fn orientation_rotation(orientation: Orientation) -> Quat {
+    match orientation {
+        Orientation::Above => Quat::from_rotation_z(std::f32::consts::PI),
+        Orientation::Below => Quat::from_rotation_z(0.),
+        Orientation::Left => Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2),
+        Orientation::Right => Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+    }
+}
+
+// Where a cell of the board is drawn, relative to the board
+pub(crate) fn cell_translation(board: &GameBoard, row: usize, col: usize) -> Vec2 {
+    Vec2::new(
+        (col as f32 * CELL_SIZE) - (CELL_SIZE * board.cols as f32) / 2.0 + CELL_SIZE / 2.0,
+        (row as f32 * CELL_SIZE) - (CELL_SIZE * board.rows as f32) / 2.0 + CELL_SIZE / 2.0,
+    )
+}
 
 #[derive(Component, Debug, Clone)]
 struct StackIndicator;
@@ -76,15 +104,7 @@ impl EntityCommand for SpritePiece {
                 }
             },
             SpritePiece::Pill(pill) => {
-                let color = match pill.0 {
-                    CellColor::RED => RED_COLOR,
-                    CellColor::YELLOW => YELLOW_COLOR,
-                    CellColor::BLUE => BLUE_COLOR,
-                    CellColor::ORANGE => RED_COLOR,
-                    CellColor::GREEN => YELLOW_COLOR,
-                    CellColor::PURPLE => BLUE_COLOR,
-                };
-                let sprite = TextureAtlasSprite {index:5, color, ..default()};
+                let sprite = TextureAtlasSprite {index:5, color: pill_color(pill.0), ..default()};
                 let transform = match (world.get::<BoardPosition>(id), world.get::<NextPill>(id)) {
                     (Some(pos), _) => { 
                         Transform::from_xyz(pos.column as f32 * CELL_SIZE, pos.row as f32 * CELL_SIZE, 100.0)
@@ -93,7 +113,7 @@ impl EntityCommand for SpritePiece {
                     (None, Some(next_index)) => {
                         Transform::from_xyz(0.0, 0.0, 100.0)
                             .with_scale(Vec3::new(0.5, 0.5, 1.0))
-                            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2 * ((next_index.0 as f32 * 2.0) + 1.)))
+                            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2 * ((next_index.half as f32 * 2.0) + 1.)))
                     },
                     _ => { return }
                 };
@@ -205,37 +225,72 @@ fn add_cleared_sprites(
     }
 }
 
+// Queued and held pills are drawn in their board's info container
+fn parent_preview_pills(
+    mut commands: Commands,
+    next_pieces: Query<(Entity, &InBoard), Or<(Added<NextPill>, Added<HeldPill>)>>,
+    containers: Query<&BoardInfoContainer>,
+) {
+    for (entity, board) in next_pieces.iter() {
+        if let Ok(container) = containers.get(**board) {
+            commands.entity(entity).set_parent(container.0);
+        }
+    }
+}
+
 fn update_transforms(
     mut query: Query<(&BoardPosition, &mut Transform, &InBoard, Option<&ClearedCell>), (Without<NextPill>, Or<(Added<Transform>, Added<BoardPosition>, Added<ClearedCell>, Changed<BoardPosition>)>)>,
-    mut next_pieces: Query<(&mut Transform, &NextPill, &InBoard), Or<(Added<NextPill>, Added<Transform>)>>,
+    mut next_pieces: Query<(&mut Transform, &NextPill, &InBoard), Or<(Changed<NextPill>, Added<Transform>)>>,
     boards: Query<&GameBoard>,
+    containers: Query<&BoardInfoContainer>,
+    sprites: Query<&Sprite>,
 ) {
     for (board_position, mut transform, board, maybe_cleared) in query.iter_mut() {
         if maybe_cleared.is_some() {
             transform.translation.z = 100.0;
         }
         let board = boards.get(**board).unwrap();
-        transform.translation.x = (board_position.column as f32 * CELL_SIZE) - (CELL_SIZE * board.cols as f32) / 2.0 + CELL_SIZE / 2.0;
-        transform.translation.y = (board_position.row as f32 * CELL_SIZE) - (CELL_SIZE * board.rows as f32) / 2.0 + CELL_SIZE / 2.0;
-        transform.translation.z = 100.0;
-        if let Some(orientation) = board
-            .get(board_position.row as usize, board_position.column as usize)
-            .get_orientation() {
-            transform.rotation = match orientation {
-                Orientation::Above => Quat::from_rotation_z(std::f32::consts::PI),
-                Orientation::Below => Quat::from_rotation_z(0.),
-                Orientation::Left => Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2),
-                Orientation::Right => Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
-            };
+        let (row, col) = (board_position.row as usize, board_position.column as usize);
+        transform.translation = cell_translation(board, row, col).extend(100.0);
+        if let Some(orientation) = board.get(row, col).get_orientation() {
+            transform.rotation = orientation_rotation(orientation);
         }
     }
 
     for (mut transform, next_pill, board) in next_pieces.iter_mut() {
-        let board = boards.get(**board).unwrap();
-        let x = ((board.cols as f32 / 2.0) + next_pill.0 as f32 - 1.5) * CELL_SIZE;
-        let y = (board.rows as f32 / 2.0 + 0.5) * CELL_SIZE;
-        transform.translation.x = x;
-        transform.translation.y = y;
-        transform.translation.z = 100.0;
+        let Some(size) = containers.get(**board).ok()
+            .and_then(|container| sprites.get(container.0).ok())
+            .and_then(|sprite| sprite.custom_size) else { continue };
+        // The next pill sits full size at the right of the container, the rest are smaller and
+        // lined up to its left under the score
+        let (x, y, scale) = if next_pill.position == 0 {
+            let x = size.x - 4.0 - CELL_SIZE * (1.5 - next_pill.half as f32);
+            (x, -size.y / 2.0, 0.5)
+        } else {
+            let right = size.x - CELL_SIZE * 2.0 - 8.0 - (next_pill.position - 1) as f32 * (CELL_SIZE + 4.0);
+            let x = right - CELL_SIZE * (0.75 - next_pill.half as f32 / 2.0);
+            (x, -size.y + 4.0 + CELL_SIZE / 4.0, 0.25)
+        };
+        transform.translation = Vec3::new(x, y, 100.0);
+        transform.scale = Vec3::new(scale, scale, 1.0);
     }
-}
\ No newline at end of file
+}
+
+type ChangedHeldPill = (Without<BoardPosition>, Or<(Changed<HeldPill>, Added<Transform>)>);
+
+// The held pill goes at the left end of the row of upcoming pills
+fn update_held_transforms(
+    mut held_pieces: Query<(&mut Transform, &HeldPill, &InBoard), ChangedHeldPill>,
+    containers: Query<&BoardInfoContainer>,
+    sprites: Query<&Sprite>,
+) {
+    for (mut transform, held_pill, board) in held_pieces.iter_mut() {
+        let Some(size) = containers.get(**board).ok()
+            .and_then(|container| sprites.get(container.0).ok())
+            .and_then(|sprite| sprite.custom_size) else { continue };
+        let x = 4.0 + CELL_SIZE * (0.25 + held_pill.half as f32 / 2.0);
+        transform.translation = Vec3::new(x, -size.y + 4.0 + CELL_SIZE / 4.0, 100.0);
+        transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2 * ((held_pill.half as f32 * 2.0) + 1.));
+        transform.scale = Vec3::new(0.25, 0.25, 1.0);
+    }
+}
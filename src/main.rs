@@ -9,6 +9,7 @@ use pills_sprites::*;
 use pills_ui::*;
 use pills_score::*;
 use pills_augments::*;
+use pills_ai::*;
 
 fn setup_camera(
     mut commands: Commands
@@ -19,10 +20,65 @@ fn setup_camera(
 fn setup_player(
     mut commands: Commands,
 ) {
-    commands.spawn(Player);
+    commands.spawn(Player(0));
 }
 
 
+// Reads `--seed <number>` so a run can be replayed
+fn seed_from_args() -> Option<FixedSeed> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--seed")?;
+    match args.get(index + 1).map(|seed| seed.parse()) {
+        Some(Ok(seed)) => Some(FixedSeed(seed)),
+        _ => {
+            eprintln!("--seed expects a number");
+            None
+        },
+    }
+}
+
+// Reads `--replay <file>` so a saved game can be watched again
+fn replay_from_args() -> Option<StartupReplay> {
+    let args: Vec<String> = std::env::args().collect();
+    let index = args.iter().position(|arg| arg == "--replay")?;
+    let Some(path) = args.get(index + 1) else {
+        eprintln!("--replay expects a file");
+        return None;
+    };
+    match load_replay(path) {
+        Ok(replay) => Some(StartupReplay(replay)),
+        Err(err) => {
+            eprintln!("Failed to load replay {}: {}", path, err);
+            None
+        },
+    }
+}
+
+// Reads `--record` so finished games are saved as replays
+fn record_from_args() -> Option<RecordReplays> {
+    std::env::args().any(|arg| arg == "--record").then_some(RecordReplays)
+}
+
+#[derive(Resource)]
+struct StartupReplay(Replay);
+
+fn start_replay(
+    mut commands: Commands,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    player_query: Query<(Entity, &Player)>,
+    replay: Option<Res<StartupReplay>>,
+) {
+    let (Some(replay), Some(player_ent)) = (replay, find_player(&player_query, 0)) else { return };
+    let rng = start_run(&mut commands, Some(&FixedSeed(replay.0.seed)));
+    let board_ent = spawn_replay_level(&mut commands, replay.0.clone());
+    commands.entity(board_ent).insert(BoardPlayer(player_ent));
+    commands.insert_resource(rng);
+    commands.remove_resource::<StartupReplay>();
+    game_state.set(GameState::Starting);
+    app_state.set(AppState::InGame);
+}
+
 fn print_state_change(
     state: Res<State<GameState>>,
 ) {
@@ -30,7 +86,17 @@ fn print_state_change(
 }
 
 fn main() {
-    App::new()
+    let mut app = App::new();
+    if let Some(seed) = seed_from_args() {
+        app.insert_resource(seed);
+    }
+    if let Some(replay) = replay_from_args() {
+        app.insert_resource(replay);
+    }
+    if let Some(record) = record_from_args() {
+        app.insert_resource(record);
+    }
+    app
         .add_plugins(DefaultPlugins)
         .add_plugins(PillsUiPlugin)
         .add_plugins(LevelPlugin)
@@ -39,6 +105,7 @@ fn main() {
         .add_plugins(ScorePlugin)
         .add_plugins(GamePlugin)
         .add_plugins(InputPlugin)
+        .add_plugins(AiPlugin)
         .add_plugins(SoundPlugin)
         .add_plugins(MenuPluginGroup)
         .add_systems(
@@ -49,6 +116,7 @@ fn main() {
                 //|mut commands: Commands| { add_augment(&mut commands, SUPERBUG); },
             )
         )
+        .add_systems(PostStartup, start_replay)
         .add_systems(
             Update, (
                 print_state_change.run_if(state_changed::<GameState>()),